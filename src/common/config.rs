@@ -1,7 +1,7 @@
 multiversx_sc::imports!();
 multiversx_sc::derive_imports!();
 
-use crate::common::errors::*;
+use crate::common::{consts::*, errors::*};
 use tfn_dao::common::config::ProxyTrait as _;
 use tfn_dex::common::config::ProxyTrait as _;
 use tfn_digital_identity::common::config::Identity;
@@ -20,6 +20,267 @@ pub enum Status {
     Active,
     Ended,
     Deployed,
+    Failed,
+    Cancelled,
+}
+
+#[type_abi]
+#[derive(ManagedVecItem, TopEncode, TopDecode, NestedEncode, NestedDecode, PartialEq, Eq, Copy, Clone, Debug)]
+pub enum SaleType {
+    FixedPrice, // first come first served at a fixed price
+    ProRata, // commitments above the hard cap are accepted and settled pro-rata after end time
+    DutchAuction, // price decays from start_price to the launchpad price. everyone settles at the clearing price
+    BondingCurve, // price grows with total_sold, starting from the launchpad price
+    FairLaunch, // buyers commit payment tokens. the price is total_raised / amount, known after end time
+    BatchAuction, // sealed bids are committed, revealed after commit_end_time and filled at a uniform clearing price
+    Lottery, // users register tickets before the sale. only drawn winners can buy, at a fixed price
+}
+
+#[type_abi]
+#[derive(ManagedVecItem, TopEncode, TopDecode, NestedEncode, NestedDecode, PartialEq, Eq, Copy, Clone, Debug)]
+pub enum PriceDecay {
+    Linear,
+    Stepwise,
+}
+
+#[type_abi]
+#[derive(ManagedVecItem, TopEncode, TopDecode, NestedEncode, NestedDecode, PartialEq, Eq, Clone, Debug)]
+pub struct DutchAuction<M: ManagedTypeApi> {
+    pub start_price: BigUint<M>, // the floor price is the launchpad price
+    pub decay: PriceDecay,
+    pub step_duration: u64, // only used for stepwise decay
+}
+
+impl<M> DutchAuction<M>
+where M: ManagedTypeApi {
+    pub fn get_price(&self, floor_price: &BigUint<M>, start_time: u64, end_time: u64, current_timestamp: u64) -> BigUint<M> {
+        if current_timestamp <= start_time {
+            return self.start_price.clone()
+        }
+        if current_timestamp >= end_time {
+            return floor_price.clone()
+        }
+
+        let mut elapsed = current_timestamp - start_time;
+        if self.decay == PriceDecay::Stepwise {
+            elapsed = elapsed / self.step_duration * self.step_duration;
+        }
+
+        &self.start_price - &((&self.start_price - floor_price) * elapsed / (end_time - start_time))
+    }
+}
+
+#[type_abi]
+#[derive(ManagedVecItem, TopEncode, TopDecode, NestedEncode, NestedDecode, PartialEq, Eq, Copy, Clone, Debug)]
+pub enum CurveType {
+    Linear,
+    Exponential,
+}
+
+#[type_abi]
+#[derive(ManagedVecItem, TopEncode, TopDecode, NestedEncode, NestedDecode, PartialEq, Eq, Clone, Debug)]
+pub struct BondingCurve<M: ManagedTypeApi> {
+    pub curve_type: CurveType,
+    pub slope: BigUint<M>, // linear: price increase per whole token sold
+    pub growth: u64, // exponential: price increase per step. 10_000 = 100%
+    pub step_size: BigUint<M>, // exponential: amount of tokens sold at the same price
+}
+
+impl<M> BondingCurve<M>
+where M: ManagedTypeApi {
+    pub fn is_valid(&self) -> bool {
+        match self.curve_type {
+            CurveType::Linear => self.slope > 0,
+            CurveType::Exponential => self.growth > 0 && self.step_size > 0,
+        }
+    }
+
+    // exponential curves may have at most MAX_CURVE_STEP_COUNT price steps over the whole sale amount
+    pub fn fits_amount(&self, amount: &BigUint<M>) -> bool {
+        match self.curve_type {
+            CurveType::Linear => true,
+            CurveType::Exponential => amount / &self.step_size <= MAX_CURVE_STEP_COUNT,
+        }
+    }
+
+    // `one` is one whole sale token (10^decimals)
+    pub fn get_spot_price(&self, base_price: &BigUint<M>, sold: &BigUint<M>, one: &BigUint<M>) -> BigUint<M> {
+        match self.curve_type {
+            CurveType::Linear => base_price + &(&self.slope * sold / one),
+            CurveType::Exponential => {
+                let step = (sold / &self.step_size).to_u64().unwrap_or(MAX_CURVE_STEP_COUNT) as u32;
+                let multiplier = BigUint::from(MAX_PERCENT + self.growth).pow(step);
+
+                base_price * &multiplier / &BigUint::from(MAX_PERCENT).pow(step)
+            },
+        }
+    }
+
+    // integrates the curve starting at `sold` and returns (tokens, cost) for at most `payment_amount`
+    // and at most `max_amount` tokens
+    pub fn get_buy_amount(
+        &self,
+        base_price: &BigUint<M>,
+        sold: &BigUint<M>,
+        payment_amount: &BigUint<M>,
+        max_amount: &BigUint<M>,
+        one: &BigUint<M>,
+    ) -> (BigUint<M>, BigUint<M>) {
+        match self.curve_type {
+            CurveType::Linear => {
+                // cost of x tokens = (2 * one * spot * x + slope * x^2) / (2 * one^2)
+                let spot_price = self.get_spot_price(base_price, sold, one);
+                let root = (&spot_price * &spot_price + &self.slope * payment_amount * 2u32).sqrt();
+                let tokens = core::cmp::min((root - &spot_price) * one / &self.slope, max_amount.clone());
+                let cost = div_ceil(
+                    &spot_price * &tokens * one * 2u32 + &self.slope * &tokens * &tokens,
+                    &(one * one * 2u32)
+                );
+
+                (tokens, cost)
+            },
+            CurveType::Exponential => {
+                let mut tokens = BigUint::zero();
+                let mut cost = BigUint::zero();
+                let mut current_sold = sold.clone();
+                let mut price = self.get_spot_price(base_price, &current_sold, one);
+                for step in 0..MAX_CURVE_STEPS {
+                    if &tokens >= max_amount {
+                        break
+                    }
+
+                    // the price of the next step is carried forward instead of recomputed from the base price
+                    if step > 0 {
+                        price = price * (MAX_PERCENT + self.growth) / MAX_PERCENT;
+                    }
+                    let step_left = (&current_sold / &self.step_size + 1u32) * &self.step_size - &current_sold;
+                    let segment = core::cmp::min(step_left, max_amount - &tokens);
+                    let segment_cost = div_ceil(&segment * &price, one);
+                    let left_payment = payment_amount - &cost;
+                    if segment_cost > left_payment {
+                        let affordable = &left_payment * one / &price;
+                        cost += div_ceil(&affordable * &price, one);
+                        tokens += affordable;
+                        break
+                    }
+
+                    cost += segment_cost;
+                    tokens += &segment;
+                    current_sold += segment;
+                }
+
+                (tokens, cost)
+            },
+        }
+    }
+}
+
+#[type_abi]
+#[derive(ManagedVecItem, TopEncode, TopDecode, NestedEncode, NestedDecode, PartialEq, Eq, Copy, Clone, Debug)]
+pub struct BatchAuction {
+    pub commit_end_time: u64, // bids are committed between start_time and commit_end_time, then revealed until end_time
+}
+
+#[type_abi]
+#[derive(ManagedVecItem, TopEncode, TopDecode, NestedEncode, NestedDecode, PartialEq, Eq, Clone, Debug)]
+pub struct Bid<M: ManagedTypeApi> {
+    pub bidder: ManagedAddress<M>,
+    pub price: BigUint<M>,
+    pub quantity: BigUint<M>,
+}
+
+#[type_abi]
+#[derive(ManagedVecItem, TopEncode, TopDecode, NestedEncode, NestedDecode, PartialEq, Eq, Clone, Debug)]
+pub struct Lottery<M: ManagedTypeApi> {
+    pub registration_end_time: u64,
+    pub winners_count: usize,
+    pub tokens_per_ticket: BigUint<M>, // governance tokens locked per ticket. if 0, every user gets one ticket
+}
+
+#[type_abi]
+#[derive(ManagedVecItem, TopEncode, TopDecode, NestedEncode, NestedDecode, PartialEq, Eq, Clone, Debug)]
+pub struct LotteryEntry<M: ManagedTypeApi> {
+    pub user: ManagedAddress<M>,
+    pub first_ticket: u64,
+    pub tickets: u64,
+}
+
+#[type_abi]
+#[derive(ManagedVecItem, TopEncode, TopDecode, NestedEncode, NestedDecode, PartialEq, Eq, Copy, Clone, Debug)]
+pub struct VestingSchedule {
+    pub tge_percent: u64, // released at claim start. 10_000 = 100%
+    pub cliff: u64, // seconds after claim start before the linear release begins
+    pub duration: u64, // seconds over which the rest is released
+    pub period: u64, // seconds between two releases
+}
+
+impl VestingSchedule {
+    pub fn is_valid(&self) -> bool {
+        self.tge_percent <= MAX_PERCENT && self.period > 0 && self.period <= self.duration
+    }
+
+    pub fn get_vested_amount<M: ManagedTypeApi>(&self, total: &BigUint<M>, start: u64, current_timestamp: u64) -> BigUint<M> {
+        if current_timestamp < start {
+            return BigUint::zero()
+        }
+
+        let tge_amount = total * self.tge_percent / MAX_PERCENT;
+        let linear_start = start + self.cliff;
+        if current_timestamp < linear_start {
+            return tge_amount
+        }
+
+        let elapsed = current_timestamp - linear_start;
+        if elapsed >= self.duration {
+            return total.clone()
+        }
+
+        let vested_time = elapsed / self.period * self.period;
+        let linear_amount = (total - &tge_amount) * vested_time / self.duration;
+
+        tge_amount + linear_amount
+    }
+
+    // returns 0 when everything is vested
+    pub fn get_next_unlock(&self, start: u64, current_timestamp: u64) -> u64 {
+        if current_timestamp < start && self.tge_percent > 0 {
+            return start
+        }
+
+        let linear_start = start + self.cliff;
+        let linear_end = linear_start + self.duration;
+        if current_timestamp >= linear_end {
+            return 0
+        }
+
+        let elapsed = current_timestamp.saturating_sub(linear_start);
+        let next_unlock = linear_start + (elapsed / self.period + 1) * self.period;
+
+        core::cmp::min(next_unlock, linear_end)
+    }
+}
+
+#[type_abi]
+#[derive(ManagedVecItem, TopEncode, TopDecode, NestedEncode, NestedDecode, PartialEq, Eq, Clone, Debug)]
+pub struct VestingStatus<M: ManagedTypeApi> {
+    pub total: BigUint<M>,
+    pub released: BigUint<M>,
+    pub claimable: BigUint<M>,
+    pub next_unlock: u64,
+}
+
+#[type_abi]
+#[derive(ManagedVecItem, TopEncode, TopDecode, NestedEncode, NestedDecode, PartialEq, Eq, Clone, Debug)]
+pub struct Purchase<M: ManagedTypeApi> {
+    pub launchpad_id: u64,
+    pub buyer: ManagedAddress<M>,
+    pub payment_token: EgldOrEsdtTokenIdentifier<M>,
+    pub paid: BigUint<M>,
+    pub refunded: BigUint<M>, // dutch auction settlements: payment returned because of a lower clearing price
+    pub tokens: BigUint<M>,
+    pub price: BigUint<M>, // for one whole token. bonding curves: the average price
+    pub timestamp: u64,
+    pub block_nonce: u64,
 }
 
 #[type_abi]
@@ -29,20 +290,51 @@ pub struct Launchpad<M: ManagedTypeApi> {
     pub owner: ManagedAddress<M>,
     pub details: Identity<M>,
     pub kyc_enforced: bool,
-    pub token: TokenIdentifier<M>, // should have 18 decimals. please check in front end
+    pub sale_type: SaleType,
+    pub token: TokenIdentifier<M>,
+    pub token_decimals: u32,
     pub amount: BigUint<M>,
-    pub payment_token: TokenIdentifier<M>,
-    pub price: BigUint<M>, // if payment token is USDC (6 decimals), price should be x_000_000
+    pub mint_cap: Option<BigUint<M>>, // if set, sold tokens are minted (up to the cap) instead of deposited
+    pub payment_token: EgldOrEsdtTokenIdentifier<M>,
+    pub payment_decimals: u32,
+    pub price: BigUint<M>, // payment token units (with payment_decimals) for one whole sale token
     pub min_buy_amount: BigUint<M>,
     pub max_buy_amount: BigUint<M>,
+    pub soft_cap: BigUint<M>, // minimum total_raised (in payment token) for the sale to succeed
     pub start_time: u64,
     pub end_time: u64,
+    pub escrow: bool, // if true, bought tokens are kept by the contract until the claim period starts
+    pub claim_start: u64, // if 0, claim starts when the franchise is deployed
+    pub vesting: Option<VestingSchedule>, // vesting starts at claim_start
     pub total_raised: BigUint<M>,
     pub total_sold: BigUint<M>,
+    pub clearing_price: BigUint<M>, // auctions: final price for all buyers. 0 until known
     pub deployed: bool,
+    pub cancelled: bool, // cancelled by the DAO, buyers can claim refunds
     pub status: Status,
 }
 
+#[type_abi]
+#[derive(ManagedVecItem, TopEncode, TopDecode, NestedEncode, NestedDecode, PartialEq, Eq, Clone, Debug)]
+pub struct Round<M: ManagedTypeApi> {
+    pub price: BigUint<M>,
+    pub start_time: u64,
+    pub end_time: u64,
+    pub min_buy_amount: BigUint<M>,
+    pub max_buy_amount: BigUint<M>,
+    pub allocation: BigUint<M>, // max amount of tokens sold in this round
+    pub whitelist_enforced: bool,
+    pub total_raised: BigUint<M>,
+    pub total_sold: BigUint<M>,
+}
+
+impl<M> Round<M>
+where M: ManagedTypeApi {
+    pub fn is_active(&self, current_timestamp: u64) -> bool {
+        current_timestamp >= self.start_time && current_timestamp <= self.end_time
+    }
+}
+
 #[type_abi]
 #[derive(ManagedVecItem, TopEncode, TopDecode, NestedEncode, NestedDecode, PartialEq, Eq, Clone, Debug)]
 pub struct LaunchpadView<M: ManagedTypeApi> {
@@ -51,18 +343,205 @@ pub struct LaunchpadView<M: ManagedTypeApi> {
     pub launchpad: Launchpad<M>,
 }
 
+#[type_abi]
+#[derive(ManagedVecItem, TopEncode, TopDecode, NestedEncode, NestedDecode, PartialEq, Eq, Clone, Debug)]
+pub struct PortfolioEntry<M: ManagedTypeApi> {
+    pub launchpad_id: u64,
+    pub token: TokenIdentifier<M>,
+    pub status: Status,
+    pub paid: ManagedVec<M, EgldOrEsdtTokenPayment<M>>, // payment token first, then the extra payment tokens
+    pub bought: BigUint<M>, // expected allocation until settled
+    pub claimed: BigUint<M>,
+    pub claimable: BigUint<M>,
+    pub next_unlock: u64,
+    pub franchise_address: Option<ManagedAddress<M>>,
+    pub whitelisted: bool,
+}
+
 impl<M> Launchpad<M>
 where M: ManagedTypeApi {
     pub fn is_active(&self, current_timestamp: u64) -> bool {
-        current_timestamp >= self.start_time && current_timestamp <= self.end_time && self.total_sold < self.amount
+        self.has_token() &&
+            !self.cancelled &&
+            current_timestamp >= self.start_time &&
+            current_timestamp <= self.end_time &&
+            (self.is_commitment_based() || self.total_sold < self.amount)
+    }
+
+    // buyers commit payment tokens during the sale and receive tokens only when settling
+    pub fn is_commitment_based(&self) -> bool {
+        self.sale_type == SaleType::ProRata || self.sale_type == SaleType::FairLaunch
+    }
+
+    // false until the token of a launchpad created without one is issued
+    pub fn has_token(&self) -> bool {
+        self.token.is_valid_esdt_identifier()
+    }
+
+    // minted launchpads only hold the tokens that were sold and not yet delivered
+    pub fn mints_on_buy(&self) -> bool {
+        self.mint_cap.is_some()
+    }
+
+    // tokens are sent at buy time only in non-escrowed fixed price sales. otherwise they are kept
+    // by the contract until settlement and/or claim
+    pub fn delivers_on_buy(&self) -> bool {
+        !self.needs_settlement() && !self.escrow
+    }
+
+    // buyers must call settle after end time to get their tokens and refunds
+    pub fn needs_settlement(&self) -> bool {
+        self.is_commitment_based() ||
+            self.sale_type == SaleType::DutchAuction ||
+            self.sale_type == SaleType::BatchAuction
+    }
+
+    // batch auctions set the clearing price when finalized
+    pub fn is_finalized(&self) -> bool {
+        self.sale_type != SaleType::BatchAuction || self.clearing_price > 0
+    }
+
+    // a dutch auction that did not sell out clears at the floor price. a batch auction clears at the
+    // lowest winning bid price (the launchpad price is the reserve price).
+    // a fair launch clears at total_raised / amount
+    pub fn get_clearing_price(&self) -> BigUint<M> {
+        if self.sale_type == SaleType::FairLaunch {
+            if self.amount == 0 {
+                return BigUint::zero()
+            }
+
+            return &self.total_raised * &self.get_token_unit() / &self.amount
+        }
+
+        if self.clearing_price > 0 {
+            self.clearing_price.clone()
+        } else {
+            self.price.clone()
+        }
+    }
+
+    // tokens that can be bought with `payment_amount` at `price`, rounded down
+    pub fn get_token_amount(&self, payment_amount: &BigUint<M>, price: &BigUint<M>) -> BigUint<M> {
+        payment_amount * &self.get_token_unit() / price
+    }
+
+    // payment needed for `token_amount` at `price`, rounded up
+    pub fn get_payment_amount(&self, token_amount: &BigUint<M>, price: &BigUint<M>) -> BigUint<M> {
+        div_ceil(token_amount * price, &self.get_token_unit())
+    }
+
+    // one whole sale token
+    pub fn get_token_unit(&self) -> BigUint<M> {
+        BigUint::from(10u32).pow(self.token_decimals)
+    }
+
+    // returns (sold tokens, raised payment) as they will be at the end of the sale
+    pub fn get_allocation(&self) -> (BigUint<M>, BigUint<M>) {
+        match self.sale_type {
+            SaleType::FixedPrice | SaleType::BondingCurve | SaleType::Lottery => {
+                (self.total_sold.clone(), self.total_raised.clone())
+            },
+            SaleType::ProRata => {
+                let demand = self.get_token_amount(&self.total_raised, &self.price);
+                if demand <= self.amount {
+                    (demand, self.total_raised.clone())
+                } else {
+                    let payment = self.get_payment_amount(&self.amount, &self.price);
+                    (self.amount.clone(), core::cmp::min(payment, self.total_raised.clone()))
+                }
+            },
+            SaleType::DutchAuction | SaleType::BatchAuction => {
+                let payment = self.get_payment_amount(&self.total_sold, &self.get_clearing_price());
+                (self.total_sold.clone(), core::cmp::min(payment, self.total_raised.clone()))
+            },
+            SaleType::FairLaunch => {
+                if self.total_raised == 0 {
+                    return (BigUint::zero(), BigUint::zero())
+                }
+
+                (self.amount.clone(), self.total_raised.clone())
+            },
+        }
+    }
+
+    // returns (tokens, refund) for a user that paid `paid` and bought `bought` during the sale
+    pub fn get_user_allocation(&self, paid: &BigUint<M>, bought: &BigUint<M>) -> (BigUint<M>, BigUint<M>) {
+        match self.sale_type {
+            SaleType::FixedPrice | SaleType::BondingCurve | SaleType::Lottery => (bought.clone(), BigUint::zero()),
+            SaleType::ProRata | SaleType::FairLaunch => {
+                if self.total_raised == 0 {
+                    return (BigUint::zero(), paid.clone())
+                }
+
+                let (tokens, payment) = self.get_allocation();
+                let user_tokens = tokens * paid / &self.total_raised;
+                let user_payment = div_ceil(payment * paid, &self.total_raised);
+
+                (user_tokens, paid - &user_payment)
+            },
+            SaleType::DutchAuction | SaleType::BatchAuction => {
+                let user_payment = self.get_payment_amount(bought, &self.get_clearing_price());
+
+                (bought.clone(), paid - &user_payment)
+            },
+        }
+    }
+
+    // fixed price sales may be paid in several tokens, so the sold tokens are also valued at the launchpad price
+    pub fn is_soft_cap_reached(&self) -> bool {
+        let raised = match self.sale_type {
+            SaleType::FixedPrice | SaleType::Lottery => core::cmp::max(
+                self.total_raised.clone(),
+                self.get_payment_amount(&self.total_sold, &self.price)
+            ),
+            _ => self.get_allocation().1,
+        };
+
+        raised >= self.soft_cap
+    }
+
+    pub fn is_claim_open(&self, current_timestamp: u64) -> bool {
+        self.escrow &&
+            !self.cancelled &&
+            self.claim_start > 0 &&
+            current_timestamp >= self.claim_start &&
+            current_timestamp > self.end_time &&
+            self.is_soft_cap_reached()
+    }
+
+    pub fn get_unlocked_amount(&self, total: &BigUint<M>, current_timestamp: u64) -> BigUint<M> {
+        if !self.is_claim_open(current_timestamp) {
+            return BigUint::zero()
+        }
+
+        match &self.vesting {
+            Some(vesting) => vesting.get_vested_amount(total, self.claim_start, current_timestamp),
+            None => total.clone(),
+        }
+    }
+
+    // nothing is claimable once the claims are closed again (e.g. the launchpad was cancelled after some claims)
+    pub fn get_claimable_amount(&self, total: &BigUint<M>, claimed: &BigUint<M>, current_timestamp: u64) -> BigUint<M> {
+        let unlocked = self.get_unlocked_amount(total, current_timestamp);
+        if unlocked <= *claimed {
+            return BigUint::zero()
+        }
+
+        unlocked - claimed
     }
 
     pub fn get_status(&self, current_timestamp: u64) -> Status {
-        if self.start_time <= current_timestamp && self.end_time >= current_timestamp {
+        if self.cancelled {
+            Status::Cancelled
+        } else if self.start_time <= current_timestamp && self.end_time >= current_timestamp {
             Status::Active
         } else if self.end_time < current_timestamp {
             if self.deployed {
                 Status::Deployed
+            } else if !self.is_finalized() {
+                Status::Ended
+            } else if !self.is_soft_cap_reached() {
+                Status::Failed
             } else {
                 Status::Ended
             }
@@ -72,8 +551,56 @@ where M: ManagedTypeApi {
     }
 }
 
+pub fn div_ceil<M: ManagedTypeApi>(a: BigUint<M>, b: &BigUint<M>) -> BigUint<M> {
+    (a + b - 1u32) / b
+}
+
+// inserts `id` into the ascending `ids`
+pub fn insert_sorted<M: ManagedTypeApi>(ids: &mut ManagedVec<M, u64>, id: u64) {
+    let mut low = 0;
+    let mut high = ids.len();
+    while low < high {
+        let mid = (low + high) / 2;
+        if ids.get(mid) < id {
+            low = mid + 1;
+        } else {
+            high = mid;
+        }
+    }
+    if low == ids.len() {
+        ids.push(id);
+        return
+    }
+
+    let mut sorted = ids.slice(0, low).unwrap_or_default();
+    sorted.push(id);
+    sorted.append_vec(ids.slice(low, ids.len()).unwrap_or_default());
+    *ids = sorted;
+}
+
+// parses the "NumDecimals-<n>" token property returned by the ESDT system SC
+pub fn parse_num_decimals(property: &[u8]) -> Option<u32> {
+    let digits = property.strip_prefix(NUM_DECIMALS_PREFIX)?;
+    if digits.is_empty() {
+        return None
+    }
+
+    let mut decimals = 0u32;
+    for digit in digits {
+        if !digit.is_ascii_digit() {
+            return None
+        }
+
+        decimals = decimals.checked_mul(10)?.checked_add((digit - b'0') as u32)?;
+    }
+
+    Some(decimals)
+}
+
 #[multiversx_sc::module]
-pub trait ConfigModule {
+pub trait ConfigModule:
+    crate::common::events::EventsModule
+{
     // state
     #[only_owner]
     #[endpoint(setStateActive)]
@@ -83,18 +610,25 @@ pub trait ConfigModule {
         require!(!self.digital_identity().is_empty(), ERROR_DIGITAL_IDENTITY_NOT_SET);
 
         self.state().set(State::Active);
+        self.state_change_event(State::Active);
     }
 
     #[only_owner]
     #[endpoint(setStateInactive)]
     fn set_state_inactive(&self) {
         self.state().set(State::Inactive);
+        self.state_change_event(State::Inactive);
     }
 
     #[view(getState)]
     #[storage_mapper("state")]
     fn state(&self) -> SingleValueMapper<State>;
 
+    // version of the stored launchpad encoding. 0 for contracts deployed before the soft cap
+    #[view(getStorageVersion)]
+    #[storage_mapper("storage_version")]
+    fn storage_version(&self) -> SingleValueMapper<u32>;
+
     // main dao sc address
     #[view(getMainDAO)]
     #[storage_mapper("main_dao")]
@@ -174,17 +708,36 @@ pub trait ConfigModule {
         let all_indexes = start_idx == 0 && end_idx == 0;
         let current_time = self.blockchain().get_block_timestamp();
         let mut launchpads: ManagedVec<LaunchpadView<Self::Api>> = ManagedVec::new();
+        // without an index range only the launchpads that can have the requested status are loaded
+        if all_indexes && !all_statuses {
+            for i in self.get_candidate_ids(Some(filter_status)).iter() {
+                let mut launchpad = self.launchpads(i).get();
+                launchpad.status = launchpad.get_status(current_time);
+                if launchpad.status == filter_status {
+                    launchpads.push(LaunchpadView {
+                        bought: self.user_participation(&address, i).get(),
+                        whitelisted: self.whitelisted_users(i).contains(&address) || !launchpad.kyc_enforced,
+                        launchpad,
+                    });
+                }
+            }
+
+            return launchpads
+        }
+
         let mut real_idx = 0;
-        for i in 0..self.last_launchpad_id().get() {
-            if self.launchpads(i).is_empty() {
+        for i in self.launchpad_ids().iter() {
+            if !all_indexes && real_idx > end_idx {
+                break
+            }
+            if !all_indexes && real_idx < start_idx {
+                real_idx += 1;
                 continue
             }
 
             let mut launchpad = self.launchpads(i).get();
             launchpad.status = launchpad.get_status(current_time);
-            let status_ok = all_statuses || launchpad.status == filter_status;
-            let idx_ok = all_indexes || (real_idx >= start_idx && real_idx <= end_idx);
-            if status_ok && idx_ok {
+            if all_statuses || launchpad.status == filter_status {
                 launchpads.push(LaunchpadView {
                     bought: self.user_participation(&address, i).get(),
                     whitelisted: self.whitelisted_users(i).contains(&address) || !launchpad.kyc_enforced,
@@ -197,20 +750,22 @@ pub trait ConfigModule {
         launchpads
     }
     
+    // Deployed and Cancelled are counted from their indexes, the other statuses load the candidate
+    // launchpads (see getActiveLaunchpads)
     #[view(getLaunchpadsCount)]
     fn get_launchpads_count(&self, status: OptionalValue<Status>) -> u64 {
-        let (all_statuses, filter_status) = match status {
-            OptionalValue::Some(status) => (false, status),
-            OptionalValue::None => (true, Status::Pending),
+        let filter_status = match status {
+            OptionalValue::Some(status) => status,
+            OptionalValue::None => return self.launchpad_ids().len() as u64,
         };
+        if filter_status == Status::Deployed || filter_status == Status::Cancelled {
+            return self.launchpads_by_status(filter_status).len() as u64
+        }
+
         let current_time = self.blockchain().get_block_timestamp();
         let mut count = 0;
-        for i in 0..self.last_launchpad_id().get() {
-            if self.launchpads(i).is_empty() {
-                continue
-            }
-
-            if all_statuses || self.launchpads(i).get().get_status(current_time) == filter_status {
+        for i in self.get_candidate_ids(Some(filter_status)).iter() {
+            if self.launchpads(i).get().get_status(current_time) == filter_status {
                 count += 1;
             }
         }
@@ -221,12 +776,19 @@ pub trait ConfigModule {
     #[view(getAllLaunchpadsSince)]
     fn get_all_launchpads_since(&self, timestamp: u64) -> ManagedVec<Launchpad<Self::Api>> {
         let current_time = self.blockchain().get_block_timestamp();
-        let mut launchpads: ManagedVec<Launchpad<Self::Api>> = ManagedVec::new();
-        for i in 0..self.last_launchpad_id().get() {
-            if self.launchpads(i).is_empty() {
-                continue
+        // only open and cancelled launchpads can end in the future
+        let ids = if timestamp >= current_time {
+            let mut ids = self.get_candidate_ids(Some(Status::Pending));
+            for id in self.launchpads_by_status(Status::Cancelled).iter() {
+                insert_sorted(&mut ids, id);
             }
 
+            ids
+        } else {
+            self.get_candidate_ids(None)
+        };
+        let mut launchpads: ManagedVec<Launchpad<Self::Api>> = ManagedVec::new();
+        for i in ids.iter() {
             let mut launchpad = self.launchpads(i).get();
             if launchpad.end_time > timestamp {
                 launchpad.status = launchpad.get_status(current_time);
@@ -237,15 +799,13 @@ pub trait ConfigModule {
         launchpads
     }
 
+    // served from the Pending index. launchpads that ended without any later settle, claim, refund or
+    // deploy stay there until updateStatusIndexes is called, so the cost depends on that keeper
     #[view(getActiveLaunchpads)]
     fn get_active_launchpads(&self) -> ManagedVec<Launchpad<Self::Api>> {
         let now = self.blockchain().get_block_timestamp();
         let mut launchpads: ManagedVec<Launchpad<Self::Api>> = ManagedVec::new();
-        for i in 0..self.last_launchpad_id().get() {
-            if self.launchpads(i).is_empty() {
-                continue
-            }
-
+        for i in self.launchpads_by_status(Status::Pending).iter() {
             let launchpad = self.launchpads(i).get();
             if launchpad.is_active(now) {
                 launchpads.push(launchpad);
@@ -255,17 +815,14 @@ pub trait ConfigModule {
         launchpads
     }
 
+    // served from the Ended and Pending indexes (see getActiveLaunchpads)
     #[view(getEndedLaunchpadsNotDeployed)]
     fn get_ended_launchpads_not_deployed(&self) -> ManagedVec<Launchpad<Self::Api>> {
         let now = self.blockchain().get_block_timestamp();
         let mut launchpads: ManagedVec<Launchpad<Self::Api>> = ManagedVec::new();
-        for i in 0..self.last_launchpad_id().get() {
-            if self.launchpads(i).is_empty() {
-                continue
-            }
-
+        for i in self.get_candidate_ids(Some(Status::Ended)).iter() {
             let launchpad = self.launchpads(i).get();
-            if !launchpad.deployed && !launchpad.is_active(now) {
+            if launchpad.end_time < now && launchpad.is_soft_cap_reached() {
                 launchpads.push(launchpad);
             }
         }
@@ -274,31 +831,95 @@ pub trait ConfigModule {
     }
 
     #[view(getTotalRaised)]
-    fn get_total_raised(&self) -> ManagedVec<EsdtTokenPayment<Self::Api>> {
-        let mut raised: ManagedVec<EsdtTokenPayment<Self::Api>> = ManagedVec::new();
-        for i in 0..self.last_launchpad_id().get() {
-            if self.launchpads(i).is_empty() {
-                continue
-            }
+    fn get_total_raised(&self) -> ManagedVec<EgldOrEsdtTokenPayment<Self::Api>> {
+        let mut raised: ManagedVec<EgldOrEsdtTokenPayment<Self::Api>> = ManagedVec::new();
+        for (token, amount) in self.total_raised_by_token().iter() {
+            raised.push(EgldOrEsdtTokenPayment::new(token, 0, amount));
+        }
 
-            let launchpad = self.launchpads(i).get();
-            let mut found = false;
-            for i in 0..raised.len() {
-                let mut payment = raised.get(i);
-                if payment.token_identifier == launchpad.payment_token {
-                    payment.amount += &launchpad.total_raised;
-                    let _ = raised.set(i, payment);
-                    found = true;
-                    break;
+        raised
+    }
+
+    // cursor based pagination. the cursor is the launchpad id to start from (0 for the first page) and each
+    // page returns the cursor of the next one, or 0 if there are no more launchpads
+    #[view(getLaunchpadsPage)]
+    fn get_launchpads_page(
+        &self,
+        cursor: u64,
+        page_size: usize,
+        address: ManagedAddress,
+        status: OptionalValue<Status>,
+    ) -> MultiValue2<ManagedVec<LaunchpadView<Self::Api>>, u64> {
+        let filter_status = status.into_option();
+        let (page, next_cursor) = self.get_page(cursor, page_size, |launchpad| {
+            filter_status.is_none() || filter_status == Some(launchpad.status)
+        });
+        let mut launchpads: ManagedVec<LaunchpadView<Self::Api>> = ManagedVec::new();
+        for launchpad in page.iter() {
+            launchpads.push(LaunchpadView {
+                bought: self.user_participation(&address, launchpad.id).get(),
+                whitelisted: self.whitelisted_users(launchpad.id).contains(&address) || !launchpad.kyc_enforced,
+                launchpad,
+            });
+        }
+
+        (launchpads, next_cursor).into()
+    }
+
+    #[view(getLaunchpadsSincePage)]
+    fn get_launchpads_since_page(
+        &self,
+        timestamp: u64,
+        cursor: u64,
+        page_size: usize
+    ) -> MultiValue2<ManagedVec<Launchpad<Self::Api>>, u64> {
+        self.get_page(cursor, page_size, |launchpad| launchpad.end_time > timestamp).into()
+    }
+
+    #[view(getActiveLaunchpadsPage)]
+    fn get_active_launchpads_page(&self, cursor: u64, page_size: usize) -> MultiValue2<ManagedVec<Launchpad<Self::Api>>, u64> {
+        let now = self.blockchain().get_block_timestamp();
+
+        self.get_page(cursor, page_size, |launchpad| launchpad.is_active(now)).into()
+    }
+
+    #[view(getEndedLaunchpadsNotDeployedPage)]
+    fn get_ended_launchpads_not_deployed_page(
+        &self,
+        cursor: u64,
+        page_size: usize
+    ) -> MultiValue2<ManagedVec<Launchpad<Self::Api>>, u64> {
+        let now = self.blockchain().get_block_timestamp();
+
+        self.get_page(cursor, page_size, |launchpad| {
+            !launchpad.deployed && !launchpad.cancelled && launchpad.end_time < now && launchpad.is_soft_cap_reached()
+        }).into()
+    }
+
+    // scans at most MAX_PAGE_SCAN ids starting from the cursor and returns at most page_size launchpads
+    // matching the filter, along with the next cursor
+    fn get_page<F>(&self, cursor: u64, page_size: usize, filter: F) -> (ManagedVec<Launchpad<Self::Api>>, u64)
+    where F: Fn(&Launchpad<Self::Api>) -> bool {
+        require!(page_size > 0 && page_size <= MAX_PAGE_SIZE, ERROR_WRONG_PAGE_SIZE);
+
+        let last_id = self.last_launchpad_id().get();
+        let scan_end = core::cmp::min(last_id, cursor.saturating_add(MAX_PAGE_SCAN));
+        let current_time = self.blockchain().get_block_timestamp();
+        let mut launchpads: ManagedVec<Launchpad<Self::Api>> = ManagedVec::new();
+        let mut id = cursor;
+        while id < scan_end && launchpads.len() < page_size {
+            if !self.launchpads(id).is_empty() {
+                let mut launchpad = self.launchpads(id).get();
+                launchpad.status = launchpad.get_status(current_time);
+                if filter(&launchpad) {
+                    launchpads.push(launchpad);
                 }
             }
-            if !found {
-                let payment = EsdtTokenPayment::new(launchpad.payment_token, 0, launchpad.total_raised);
-                raised.push(payment);
-            }
+            id += 1;
         }
+        let next_cursor = if id < last_id { id } else { 0 };
 
-        raised
+        (launchpads, next_cursor)
     }
 
     #[view(getLastLaunchpadId)]
@@ -309,20 +930,24 @@ pub trait ConfigModule {
     #[storage_mapper("token_launchpad_id")]
     fn token_launchpad_id(&self, token: &TokenIdentifier) -> SingleValueMapper<u64>;
 
-    #[view(isTokenLaunched)]
-    fn is_token_launched(&self, token: TokenIdentifier) -> bool {
-        for launchpad_id in 0..self.last_launchpad_id().get() {
-            if self.launchpads(launchpad_id).is_empty() {
-                continue
-            }
+    // true if the launchpad token was (or is being) issued by this contract
+    #[view(isTokenIssued)]
+    #[storage_mapper("token_issued")]
+    fn token_issued(&self, id: u64) -> SingleValueMapper<bool>;
 
-            let launchpad = self.launchpads(launchpad_id).get();
-            if launchpad.token == token {
-                return true
-            }
-        }
+    // true once the ownership of an issued token was transferred to the franchise DAO
+    #[view(isTokenReleased)]
+    #[storage_mapper("token_released")]
+    fn token_released(&self, id: u64) -> SingleValueMapper<bool>;
 
-        false
+    // sale tokens minted by minted launchpads
+    #[view(getMintedTokens)]
+    #[storage_mapper("minted_tokens")]
+    fn minted_tokens(&self, id: u64) -> SingleValueMapper<BigUint>;
+
+    #[view(isTokenLaunched)]
+    fn is_token_launched(&self, token: TokenIdentifier) -> bool {
+        self.launched_tokens().contains(&token)
     }
 
     #[view(getLaunchpadUsers)]
@@ -337,6 +962,133 @@ pub trait ConfigModule {
     #[storage_mapper("user_participation")]
     fn user_participation(&self, user: &ManagedAddress, id: u64) -> SingleValueMapper<BigUint>;
 
+    #[view(getUserPayment)]
+    #[storage_mapper("user_payment")]
+    fn user_payment(&self, user: &ManagedAddress, id: u64) -> SingleValueMapper<BigUint>;
+
+    #[view(isUserSettled)]
+    #[storage_mapper("user_settled")]
+    fn user_settled(&self, user: &ManagedAddress, id: u64) -> SingleValueMapper<bool>;
+
+    // returns (tokens, refund) the user receives when settling
+    #[view(getSettlementAmounts)]
+    fn get_settlement_amounts(&self, id: u64, user: ManagedAddress) -> MultiValue2<BigUint, BigUint> {
+        require!(!self.launchpads(id).is_empty(), ERROR_LAUNCHPAD_NOT_FOUND);
+
+        let launchpad = self.launchpads(id).get();
+        require!(launchpad.needs_settlement(), ERROR_WRONG_SALE_TYPE);
+
+        if self.user_settled(&user, id).get() {
+            return (BigUint::zero(), BigUint::zero()).into()
+        }
+
+        launchpad.get_user_allocation(
+            &self.user_payment(&user, id).get(),
+            &self.user_participation(&user, id).get()
+        ).into()
+    }
+
+    #[view(getUserClaimed)]
+    #[storage_mapper("user_claimed")]
+    fn user_claimed(&self, user: &ManagedAddress, id: u64) -> SingleValueMapper<BigUint>;
+
+    // returns (claimable now, already claimed)
+    #[view(getClaimStatus)]
+    fn get_claim_status(&self, id: u64, user: ManagedAddress) -> MultiValue2<BigUint, BigUint> {
+        require!(!self.launchpads(id).is_empty(), ERROR_LAUNCHPAD_NOT_FOUND);
+
+        let launchpad = self.launchpads(id).get();
+        let bought = self.user_participation(&user, id).get();
+        let claimed = self.user_claimed(&user, id).get();
+        let claimable = launchpad.get_claimable_amount(&bought, &claimed, self.blockchain().get_block_timestamp());
+
+        (claimable, claimed).into()
+    }
+
+    #[view(getVestingStatus)]
+    fn get_vesting_status(&self, id: u64, user: ManagedAddress) -> VestingStatus<Self::Api> {
+        require!(!self.launchpads(id).is_empty(), ERROR_LAUNCHPAD_NOT_FOUND);
+
+        let launchpad = self.launchpads(id).get();
+        require!(launchpad.vesting.is_some(), ERROR_NOT_VESTED);
+
+        let now = self.blockchain().get_block_timestamp();
+        let total = self.user_participation(&user, id).get();
+        let released = self.user_claimed(&user, id).get();
+        let claimable = launchpad.get_claimable_amount(&total, &released, now);
+        let next_unlock = match (&launchpad.vesting, launchpad.claim_start) {
+            (Some(vesting), claim_start) if claim_start > 0 => vesting.get_next_unlock(claim_start, now),
+            _ => 0,
+        };
+
+        VestingStatus {
+            total,
+            released,
+            claimable,
+            next_unlock,
+        }
+    }
+
+    #[view(getUserPortfolio)]
+    fn get_user_portfolio(&self, user: ManagedAddress) -> ManagedVec<PortfolioEntry<Self::Api>> {
+        let now = self.blockchain().get_block_timestamp();
+        let mut portfolio: ManagedVec<PortfolioEntry<Self::Api>> = ManagedVec::new();
+        for id in self.user_launchpads(&user).iter() {
+            if self.launchpads(id).is_empty() {
+                continue
+            }
+
+            let launchpad = self.launchpads(id).get();
+            let payment = self.user_payment(&user, id).get();
+            let mut paid: ManagedVec<EgldOrEsdtTokenPayment<Self::Api>> = ManagedVec::new();
+            paid.push(EgldOrEsdtTokenPayment::new(launchpad.payment_token.clone(), 0, payment.clone()));
+            for token in self.payment_token_prices(id).keys() {
+                let amount = self.user_token_payment(&user, id, &token).get();
+                if amount > 0 {
+                    paid.push(EgldOrEsdtTokenPayment::new(token, 0, amount));
+                }
+            }
+
+            // claims are based on the participation, which is only final after settling
+            let participation = self.user_participation(&user, id).get();
+            let claimed = self.user_claimed(&user, id).get();
+            let claimable = launchpad.get_claimable_amount(&participation, &claimed, now);
+            let bought = if launchpad.needs_settlement() && !self.user_settled(&user, id).get() && launchpad.is_finalized() {
+                launchpad.get_user_allocation(&payment, &participation).0
+            } else {
+                participation
+            };
+            let next_unlock = match (&launchpad.vesting, launchpad.claim_start) {
+                (Some(vesting), claim_start) if claim_start > 0 => vesting.get_next_unlock(claim_start, now),
+                _ => 0,
+            };
+            let franchise_address = if self.franchise_address(id).is_empty() {
+                None
+            } else {
+                Some(self.franchise_address(id).get())
+            };
+
+            portfolio.push(PortfolioEntry {
+                launchpad_id: id,
+                token: launchpad.token.clone(),
+                status: launchpad.get_status(now),
+                paid,
+                bought,
+                claimed,
+                claimable,
+                next_unlock,
+                franchise_address,
+                whitelisted: self.whitelisted_users(id).contains(&user) || !launchpad.kyc_enforced,
+            });
+        }
+
+        portfolio
+    }
+
+    #[view(getFranchiseAddress)]
+    #[storage_mapper("franchise_address")]
+    fn franchise_address(&self, id: u64) -> SingleValueMapper<ManagedAddress>;
+
     #[view(getDeployedLaunchpadId)]
     #[storage_mapper("deployed_launchpads")]
     fn deployed_launchpads(&self, address: ManagedAddress) -> SingleValueMapper<u64>;
@@ -351,11 +1103,480 @@ pub trait ConfigModule {
     #[storage_mapper("whitelisted_users")]
     fn whitelisted_users(&self, id: u64) -> UnorderedSetMapper<ManagedAddress>;
 
+    // extra payment tokens and their prices (the launchpad payment token and price are the primary ones)
+    #[view(getPaymentTokenPrices)]
+    #[storage_mapper("payment_token_prices")]
+    fn payment_token_prices(&self, id: u64) -> MapMapper<EgldOrEsdtTokenIdentifier, BigUint>;
+
+    #[view(getTokenRaised)]
+    #[storage_mapper("token_raised")]
+    fn token_raised(&self, id: u64, token: &EgldOrEsdtTokenIdentifier) -> SingleValueMapper<BigUint>;
+
+    #[view(getUserTokenPayment)]
+    #[storage_mapper("user_token_payment")]
+    fn user_token_payment(&self, user: &ManagedAddress, id: u64, token: &EgldOrEsdtTokenIdentifier) -> SingleValueMapper<BigUint>;
+
+    #[view(getPaymentTokens)]
+    fn get_payment_tokens(&self, id: u64) -> MultiValueEncoded<MultiValue2<EgldOrEsdtTokenIdentifier, BigUint>> {
+        require!(!self.launchpads(id).is_empty(), ERROR_LAUNCHPAD_NOT_FOUND);
+
+        let launchpad = self.launchpads(id).get();
+        let mut tokens = MultiValueEncoded::new();
+        tokens.push((launchpad.payment_token, launchpad.price).into());
+        for (token, price) in self.payment_token_prices(id).iter() {
+            tokens.push((token, price).into());
+        }
+
+        tokens
+    }
+
+    // returns the price of the payment token or fails if the launchpad does not accept it
+    fn get_payment_token_price(&self, launchpad: &Launchpad<Self::Api>, token: &EgldOrEsdtTokenIdentifier) -> BigUint {
+        if *token == launchpad.payment_token {
+            return launchpad.price.clone()
+        }
+
+        match self.payment_token_prices(launchpad.id).get(token) {
+            Some(price) => price,
+            None => sc_panic!(ERROR_WRONG_TOKEN),
+        }
+    }
+
+    #[view(getLaunchpadRaised)]
+    fn get_launchpad_raised(&self, id: u64) -> ManagedVec<EgldOrEsdtTokenPayment<Self::Api>> {
+        require!(!self.launchpads(id).is_empty(), ERROR_LAUNCHPAD_NOT_FOUND);
+
+        let launchpad = self.launchpads(id).get();
+        let mut raised: ManagedVec<EgldOrEsdtTokenPayment<Self::Api>> = ManagedVec::new();
+        raised.push(EgldOrEsdtTokenPayment::new(launchpad.payment_token, 0, launchpad.total_raised));
+        for token in self.payment_token_prices(id).keys() {
+            let amount = self.token_raised(id, &token).get();
+            raised.push(EgldOrEsdtTokenPayment::new(token, 0, amount));
+        }
+
+        raised
+    }
+
+    // token decimals, loaded from the ESDT system SC
+    #[view(getTokenDecimals)]
+    #[storage_mapper("token_decimals")]
+    fn token_decimals(&self) -> MapMapper<TokenIdentifier, u32>;
+
+    fn get_token_decimals(&self, token: &EgldOrEsdtTokenIdentifier) -> u32 {
+        if token.is_egld() {
+            return EGLD_DECIMALS
+        }
+
+        match self.token_decimals().get(&token.clone().unwrap_esdt()) {
+            Some(decimals) => decimals,
+            None => sc_panic!(ERROR_UNKNOWN_DECIMALS),
+        }
+    }
+
+    // indexes
+    // in id order, since ids only grow
+    #[view(getLaunchpadIds)]
+    #[storage_mapper("launchpad_ids")]
+    fn launchpad_ids(&self) -> SetMapper<u64>;
+
+    // launchpads are indexed by their last known status, with Active ones indexed as Pending. launchpads that
+    // ended or failed by time move out of Pending when they are settled, claimed, refunded, finalized or
+    // deployed, or with updateStatusIndexes, so the actual status still depends on the current time
+    #[view(getLaunchpadIdsByStatus)]
+    #[storage_mapper("launchpads_by_status")]
+    fn launchpads_by_status(&self, status: Status) -> UnorderedSetMapper<u64>;
+
+    #[view(getOwnerLaunchpads)]
+    #[storage_mapper("owner_launchpads")]
+    fn owner_launchpads(&self, owner: &ManagedAddress) -> UnorderedSetMapper<u64>;
+
+    #[view(getPaymentTokenLaunchpads)]
+    #[storage_mapper("payment_token_launchpads")]
+    fn payment_token_launchpads(&self, token: &EgldOrEsdtTokenIdentifier) -> UnorderedSetMapper<u64>;
+
+    #[storage_mapper("launched_tokens")]
+    fn launched_tokens(&self) -> UnorderedSetMapper<TokenIdentifier>;
+
+    // sum of the raised amounts of all existing launchpads
+    #[storage_mapper("total_raised_by_token")]
+    fn total_raised_by_token(&self) -> MapMapper<EgldOrEsdtTokenIdentifier, BigUint>;
+
+    // ids of the launchpads that can have the given status (all launchpads if None), in id order
+    fn get_candidate_ids(&self, status: Option<Status>) -> ManagedVec<u64> {
+        let mut ids: ManagedVec<u64> = ManagedVec::new();
+        let indexes: &[Status] = match status {
+            None => {
+                for id in self.launchpad_ids().iter() {
+                    ids.push(id);
+                }
+
+                return ids
+            },
+            Some(Status::Deployed) => &[Status::Deployed],
+            Some(Status::Cancelled) => &[Status::Cancelled],
+            Some(Status::Failed) => &[Status::Failed, Status::Ended, Status::Pending],
+            Some(Status::Ended) => &[Status::Ended, Status::Pending],
+            Some(_) => &[Status::Pending],
+        };
+        for index in indexes {
+            for id in self.launchpads_by_status(*index).iter() {
+                insert_sorted(&mut ids, id);
+            }
+        }
+
+        ids
+    }
+
+    fn index_launchpad(&self, launchpad: &Launchpad<Self::Api>) {
+        self.launchpad_ids().insert(launchpad.id);
+        self.launchpads_by_status(Status::Pending).insert(launchpad.id);
+        self.owner_launchpads(&launchpad.owner).insert(launchpad.id);
+        self.payment_token_launchpads(&launchpad.payment_token).insert(launchpad.id);
+        if launchpad.has_token() {
+            self.launched_tokens().insert(launchpad.token.clone());
+        }
+    }
+
+    // must be called before clearing the launchpad payment tokens
+    fn unindex_launchpad(&self, launchpad: &Launchpad<Self::Api>) {
+        self.launchpad_ids().remove(&launchpad.id);
+        for status in [Status::Pending, Status::Ended, Status::Deployed, Status::Failed, Status::Cancelled] {
+            self.launchpads_by_status(status).swap_remove(&launchpad.id);
+        }
+        self.owner_launchpads(&launchpad.owner).swap_remove(&launchpad.id);
+        self.payment_token_launchpads(&launchpad.payment_token).swap_remove(&launchpad.id);
+        for token in self.payment_token_prices(launchpad.id).keys() {
+            self.payment_token_launchpads(&token).swap_remove(&launchpad.id);
+        }
+        self.launched_tokens().swap_remove(&launchpad.token);
+    }
+
+    // moves a launchpad that is not deployed or cancelled yet to the index of the given status
+    fn set_status_index(&self, id: u64, status: Status) {
+        let index = if status == Status::Active { Status::Pending } else { status };
+        for open_status in [Status::Pending, Status::Ended, Status::Failed] {
+            if open_status != index && self.launchpads_by_status(open_status).swap_remove(&id) {
+                self.launchpads_by_status(index).insert(id);
+                return
+            }
+        }
+    }
+
+    fn refresh_status_index(&self, launchpad: &Launchpad<Self::Api>) {
+        self.set_status_index(launchpad.id, launchpad.get_status(self.blockchain().get_block_timestamp()));
+    }
+
+    fn add_total_raised(&self, token: &EgldOrEsdtTokenIdentifier, amount: &BigUint) {
+        let mut mapper = self.total_raised_by_token();
+        let raised = mapper.get(token).unwrap_or_default() + amount;
+        mapper.insert(token.clone(), raised);
+    }
+
+    fn sub_total_raised(&self, token: &EgldOrEsdtTokenIdentifier, amount: &BigUint) {
+        let mut mapper = self.total_raised_by_token();
+        let raised = mapper.get(token).unwrap_or_default() - amount;
+        mapper.insert(token.clone(), raised);
+    }
+
+    // rounds (ids start from 1)
+    #[storage_mapper("rounds")]
+    fn rounds(&self, id: u64) -> VecMapper<Round<Self::Api>>;
+
+    #[view(getRounds)]
+    fn get_rounds(&self, id: u64) -> ManagedVec<Round<Self::Api>> {
+        let mut rounds: ManagedVec<Round<Self::Api>> = ManagedVec::new();
+        for round in self.rounds(id).iter() {
+            rounds.push(round);
+        }
+
+        rounds
+    }
+
+    // returns 0 if no round is active
+    #[view(getActiveRoundId)]
+    fn get_active_round_id(&self, id: u64) -> usize {
+        let now = self.blockchain().get_block_timestamp();
+        for (round_id, round) in self.rounds(id).iter().enumerate() {
+            if round.is_active(now) {
+                return round_id + 1
+            }
+        }
+
+        0
+    }
+
+    #[view(getRoundWhitelistedUsers)]
+    #[storage_mapper("round_whitelisted_users")]
+    fn round_whitelisted_users(&self, id: u64, round_id: usize) -> UnorderedSetMapper<ManagedAddress>;
+
+    // dutch auctions
+    #[view(getDutchAuction)]
+    #[storage_mapper("dutch_auctions")]
+    fn dutch_auctions(&self, id: u64) -> SingleValueMapper<DutchAuction<Self::Api>>;
+
+    fn get_dutch_auction_price(&self, launchpad: &Launchpad<Self::Api>, current_timestamp: u64) -> BigUint {
+        require!(!self.dutch_auctions(launchpad.id).is_empty(), ERROR_SALE_NOT_CONFIGURED);
+
+        self.dutch_auctions(launchpad.id).get().get_price(
+            &launchpad.price,
+            launchpad.start_time,
+            launchpad.end_time,
+            current_timestamp
+        )
+    }
+
+    // bonding curves
+    #[view(getBondingCurve)]
+    #[storage_mapper("bonding_curves")]
+    fn bonding_curves(&self, id: u64) -> SingleValueMapper<BondingCurve<Self::Api>>;
+
+    fn get_bonding_curve(&self, id: u64) -> BondingCurve<Self::Api> {
+        require!(!self.bonding_curves(id).is_empty(), ERROR_SALE_NOT_CONFIGURED);
+
+        self.bonding_curves(id).get()
+    }
+
+    // returns (tokens, cost) for buying with `payment_amount` now, without user limits
+    #[view(quoteBuy)]
+    fn quote_buy(&self, id: u64, payment_amount: BigUint) -> MultiValue2<BigUint, BigUint> {
+        require!(!self.launchpads(id).is_empty(), ERROR_LAUNCHPAD_NOT_FOUND);
+
+        let launchpad = self.launchpads(id).get();
+        require!(!launchpad.needs_settlement() || launchpad.sale_type == SaleType::DutchAuction, ERROR_WRONG_SALE_TYPE);
+
+        let left_amount = &launchpad.amount - &launchpad.total_sold;
+        if launchpad.sale_type == SaleType::BondingCurve {
+            return self.get_bonding_curve(id)
+                .get_buy_amount(&launchpad.price, &launchpad.total_sold, &payment_amount, &left_amount, &launchpad.get_token_unit())
+                .into()
+        }
+
+        let price = self.get_current_price(id);
+        let token_amount = core::cmp::min(launchpad.get_token_amount(&payment_amount, &price), left_amount);
+        let cost = launchpad.get_payment_amount(&token_amount, &price);
+
+        (token_amount, cost).into()
+    }
+
+    #[view(getCurrentPrice)]
+    fn get_current_price(&self, id: u64) -> BigUint {
+        require!(!self.launchpads(id).is_empty(), ERROR_LAUNCHPAD_NOT_FOUND);
+
+        let launchpad = self.launchpads(id).get();
+        match launchpad.sale_type {
+            SaleType::DutchAuction => self.get_dutch_auction_price(&launchpad, self.blockchain().get_block_timestamp()),
+            SaleType::BondingCurve => self.get_bonding_curve(id)
+                .get_spot_price(&launchpad.price, &launchpad.total_sold, &launchpad.get_token_unit()),
+            SaleType::FairLaunch | SaleType::BatchAuction => launchpad.get_clearing_price(),
+            _ => {
+                let round_id = self.get_active_round_id(id);
+                if round_id > 0 {
+                    self.rounds(id).get(round_id).price
+                } else {
+                    launchpad.price
+                }
+            },
+        }
+    }
+
+    // batch auctions
+    #[view(getBatchAuction)]
+    #[storage_mapper("batch_auctions")]
+    fn batch_auctions(&self, id: u64) -> SingleValueMapper<BatchAuction>;
+
+    fn get_batch_auction(&self, id: u64) -> BatchAuction {
+        require!(!self.batch_auctions(id).is_empty(), ERROR_SALE_NOT_CONFIGURED);
+
+        self.batch_auctions(id).get()
+    }
+
+    #[view(getBidHash)]
+    #[storage_mapper("bid_hashes")]
+    fn bid_hashes(&self, id: u64, user: &ManagedAddress) -> SingleValueMapper<ManagedByteArray<Self::Api, 32>>;
+
+    // in reveal order. at most MAX_AUCTION_BIDS bidders can commit
+    #[view(getRevealedBids)]
+    #[storage_mapper("revealed_bids")]
+    fn revealed_bids(&self, id: u64) -> VecMapper<Bid<Self::Api>>;
+
+    // keccak256 of the nested encoded price and quantity followed by the salt
+    #[view(computeBidHash)]
+    fn compute_bid_hash(&self, price: BigUint, quantity: BigUint, salt: ManagedBuffer) -> ManagedByteArray<Self::Api, 32> {
+        let mut data = ManagedBuffer::new();
+        let _ = price.dep_encode(&mut data);
+        let _ = quantity.dep_encode(&mut data);
+        data.append(&salt);
+
+        self.crypto().keccak256(data)
+    }
+
+    // lotteries
+    #[view(getLottery)]
+    #[storage_mapper("lotteries")]
+    fn lotteries(&self, id: u64) -> SingleValueMapper<Lottery<Self::Api>>;
+
+    fn get_lottery(&self, id: u64) -> Lottery<Self::Api> {
+        require!(!self.lotteries(id).is_empty(), ERROR_SALE_NOT_CONFIGURED);
+
+        self.lotteries(id).get()
+    }
+
+    #[view(getLotteryEntries)]
+    #[storage_mapper("lottery_entries")]
+    fn lottery_entries(&self, id: u64) -> VecMapper<LotteryEntry<Self::Api>>;
+
+    #[storage_mapper("lottery_entry_index")]
+    fn lottery_entry_index(&self, id: u64, user: &ManagedAddress) -> SingleValueMapper<usize>;
+
+    #[view(getLotteryTotalTickets)]
+    #[storage_mapper("lottery_total_tickets")]
+    fn lottery_total_tickets(&self, id: u64) -> SingleValueMapper<u64>;
+
+    #[view(getLotteryStake)]
+    #[storage_mapper("lottery_stakes")]
+    fn lottery_stakes(&self, id: u64, user: &ManagedAddress) -> SingleValueMapper<BigUint>;
+
+    #[view(getWinners)]
+    #[storage_mapper("lottery_winners")]
+    fn lottery_winners(&self, id: u64) -> UnorderedSetMapper<ManagedAddress>;
+
+    #[view(isWinner)]
+    fn is_winner(&self, id: u64, user: ManagedAddress) -> bool {
+        self.lottery_winners(id).contains(&user)
+    }
+
+    fn is_lottery_drawn(&self, id: u64) -> bool {
+        let winners_count = core::cmp::min(self.get_lottery(id).winners_count, self.lottery_entries(id).len());
+
+        self.lottery_winners(id).len() >= winners_count
+    }
+
+    // returns the index of the entry holding the ticket
+    fn find_lottery_entry(&self, id: u64, ticket: u64) -> usize {
+        let entries = self.lottery_entries(id);
+        let mut low = 1;
+        let mut high = entries.len();
+        while low < high {
+            let mid = (low + high + 1) / 2;
+            if entries.get(mid).first_ticket <= ticket {
+                low = mid;
+            } else {
+                high = mid - 1;
+            }
+        }
+
+        low
+    }
+
+    // purchase history. settlements of batch auctions and commitment based sales are recorded as purchases.
+    // dutch auction settlements only record the refunded payment (without tokens)
+    #[storage_mapper("purchases")]
+    fn purchases(&self, id: u64) -> VecMapper<Purchase<Self::Api>>;
+
+    // (launchpad id, index in the launchpad purchases)
+    #[storage_mapper("user_purchases")]
+    fn user_purchases(&self, user: &ManagedAddress) -> VecMapper<(u64, usize)>;
+
+    #[view(getPurchasesCount)]
+    fn get_purchases_count(&self, id: u64) -> usize {
+        self.purchases(id).len()
+    }
+
+    #[view(getUserPurchasesCount)]
+    fn get_user_purchases_count(&self, user: ManagedAddress) -> usize {
+        self.user_purchases(&user).len()
+    }
+
+    // `from` starts at 0
+    #[view(getPurchases)]
+    fn get_purchases(&self, id: u64, from: usize, count: usize) -> ManagedVec<Purchase<Self::Api>> {
+        require!(count <= MAX_PAGE_SIZE, ERROR_WRONG_PAGE_SIZE);
+
+        let mut purchases: ManagedVec<Purchase<Self::Api>> = ManagedVec::new();
+        let end = core::cmp::min(from.saturating_add(count), self.purchases(id).len());
+        for index in from..end {
+            purchases.push(self.purchases(id).get(index + 1));
+        }
+
+        purchases
+    }
+
+    #[view(getUserPurchases)]
+    fn get_user_purchases(&self, user: ManagedAddress, from: usize, count: usize) -> ManagedVec<Purchase<Self::Api>> {
+        require!(count <= MAX_PAGE_SIZE, ERROR_WRONG_PAGE_SIZE);
+
+        let mut purchases: ManagedVec<Purchase<Self::Api>> = ManagedVec::new();
+        let end = core::cmp::min(from.saturating_add(count), self.user_purchases(&user).len());
+        for index in from..end {
+            let (id, purchase_index) = self.user_purchases(&user).get(index + 1);
+            purchases.push(self.purchases(id).get(purchase_index));
+        }
+
+        purchases
+    }
+
+    fn record_purchase(
+        &self,
+        id: u64,
+        buyer: &ManagedAddress,
+        payment_token: &EgldOrEsdtTokenIdentifier,
+        paid: &BigUint,
+        refunded: &BigUint,
+        tokens: &BigUint,
+        price: BigUint
+    ) {
+        let index = self.purchases(id).push(&Purchase {
+            launchpad_id: id,
+            buyer: buyer.clone(),
+            payment_token: payment_token.clone(),
+            paid: paid.clone(),
+            refunded: refunded.clone(),
+            tokens: tokens.clone(),
+            price,
+            timestamp: self.blockchain().get_block_timestamp(),
+            block_nonce: self.blockchain().get_block_nonce(),
+        });
+        self.user_purchases(buyer).push(&(id, index));
+    }
+
+    #[view(getUserRoundParticipation)]
+    #[storage_mapper("user_round_participation")]
+    fn user_round_participation(&self, user: &ManagedAddress, id: u64, round_id: usize) -> SingleValueMapper<BigUint>;
+
     // helpers
     fn only_dao(&self) {
         require!(self.blockchain().get_caller() == self.main_dao().get(), ERROR_ONLY_MAIN_DAO);
     }
 
+    fn send_sale_tokens(&self, launchpad: &Launchpad<Self::Api>, to: &ManagedAddress, amount: &BigUint) {
+        if *amount == 0 {
+            return
+        }
+
+        // deployed launchpads minted all the sold tokens at deploy
+        if launchpad.mints_on_buy() && !launchpad.deployed {
+            self.send().esdt_local_mint(&launchpad.token, 0, amount);
+            self.minted_tokens(launchpad.id).update(|value| *value += amount);
+        }
+        self.send().direct_esdt(to, &launchpad.token, 0, amount);
+    }
+
+    fn has_mint_roles(&self, token: &TokenIdentifier) -> bool {
+        let roles = self.blockchain().get_esdt_local_roles(token);
+
+        roles.has_role(&EsdtLocalRole::Mint) && roles.has_role(&EsdtLocalRole::Burn)
+    }
+
+    fn only_dao_or_launchpad_owner(&self, id: u64) {
+        require!(!self.launchpads(id).is_empty(), ERROR_LAUNCHPAD_NOT_FOUND);
+
+        let caller = self.blockchain().get_caller();
+        require!(
+            caller == self.main_dao().get() || caller == self.launchpads(id).get().owner,
+            ERROR_ONLY_DAO_OR_LAUNCHPAD_OWNER
+        );
+    }
+
     fn only_launchpad_owner(&self, id: u64) {
         require!(!self.launchpads(id).is_empty(), ERROR_LAUNCHPAD_NOT_FOUND);
 
@@ -1,2 +1,13 @@
-pub const ONE: u64 = 1_000_000_000_000_000_000;
+pub const EGLD_DECIMALS: u32 = 18;
+pub const MAX_DECIMALS: u32 = 18;
+pub const NUM_DECIMALS_PREFIX: &[u8] = b"NumDecimals-";
+pub const MAX_TOKEN_PROPERTY_LENGTH: usize = 64;
 pub const GAS_LIMIT_FOR_CREATE_PAIR: u64 = 100_000_000;
+pub const MAX_PERCENT: u64 = 10_000;
+pub const MAX_CURVE_STEPS: usize = 50;
+pub const MAX_CURVE_STEP_COUNT: u64 = 1_000;
+pub const MAX_AUCTION_BIDS: usize = 100;
+pub const MAX_DRAW_ATTEMPTS: usize = 100;
+pub const MAX_PAGE_SIZE: usize = 50;
+pub const MAX_PAGE_SCAN: u64 = 500;
+pub const STORAGE_VERSION: u32 = 1;
@@ -1,21 +1,77 @@
 pub static ERROR_ONLY_MAIN_DAO: &[u8] = b"only the main DAO can execute this action";
 pub static ERROR_ONLY_LAUNCHPAD_OWNER: &[u8] = b"only the launchpad owner can execute this action";
+pub static ERROR_ONLY_DAO_OR_LAUNCHPAD_OWNER: &[u8] = b"only the main DAO or the launchpad owner can execute this action";
 pub static ERROR_NOT_ACTIVE: &[u8] = b"contract is paused";
 pub static ERROR_WRONG_START_TIME: &[u8] = b"start time can not be in the past";
 pub static ERROR_WRONG_END_TIME: &[u8] = b"end time must be after start time";
+pub static ERROR_WRONG_CLAIM_START: &[u8] = b"claim start can not be before end time";
 pub static ERROR_TOKEN_ALREADY_LAUNCHED: &[u8] = b"token already launched";
 pub static ERROR_WRONG_MIN_MAX_AMOUNTS: &[u8] = b"max buy amount must be greater than min buy amount";
 pub static ERROR_ZERO_PRICE: &[u8] = b"price can not be zero";
+pub static ERROR_LAUNCHPAD_STARTED: &[u8] = b"launchpad already started";
+pub static ERROR_WRONG_ROUND_TIME: &[u8] = b"round must be inside the launchpad time window";
+pub static ERROR_OVERLAPPING_ROUNDS: &[u8] = b"round must start after the previous round ends";
+pub static ERROR_ROUND_NOT_FOUND: &[u8] = b"a round with this id does not exist";
+pub static ERROR_NO_ACTIVE_ROUND: &[u8] = b"no round is currently active";
 pub static ERROR_LAUNCHPAD_NOT_FOUND: &[u8] = b"a launchpad with this id does not exist";
 pub static ERROR_LAUNCHPAD_INACTIVE: &[u8] = b"launchpad not active";
 pub static ERROR_WRONG_TOKEN: &[u8] = b"wrong payment token";
 pub static ERROR_LOW_AMOUNT: &[u8] = b"must buy at least min amount";
 pub static ERROR_HIGH_AMOUNT: &[u8] = b"total user bought amount exceeds max amount";
 pub static ERROR_INSUFFICIENT_FUNDS: &[u8] = b"insufficient funds left in contract";
+pub static ERROR_ZERO_AMOUNT: &[u8] = b"payment too low to buy any tokens";
 pub static ERROR_NOT_WHITELISTED: &[u8] = b"user not whitelisted";
+pub static ERROR_WRONG_SALE_TYPE: &[u8] = b"action not supported for this sale type";
+pub static ERROR_SALE_NOT_CONFIGURED: &[u8] = b"sale parameters not configured";
+pub static ERROR_WRONG_START_PRICE: &[u8] = b"start price must be greater than the floor price";
+pub static ERROR_WRONG_STEP_DURATION: &[u8] = b"invalid price step duration";
+pub static ERROR_WRONG_CURVE: &[u8] = b"invalid bonding curve parameters";
+pub static ERROR_SLIPPAGE: &[u8] = b"token amount lower than the minimum requested";
+pub static ERROR_WRONG_COMMIT_END_TIME: &[u8] = b"commit end time must be between start time and end time";
+pub static ERROR_NOT_COMMIT_PERIOD: &[u8] = b"bids can not be committed now";
+pub static ERROR_NOT_REVEAL_PERIOD: &[u8] = b"bids can not be revealed now";
+pub static ERROR_BID_NOT_FOUND: &[u8] = b"no committed bid";
+pub static ERROR_WRONG_BID_HASH: &[u8] = b"revealed bid does not match the committed hash";
+pub static ERROR_LOW_BID_PRICE: &[u8] = b"bid price lower than the reserve price";
+pub static ERROR_INSUFFICIENT_DEPOSIT: &[u8] = b"deposit does not cover the bid";
+pub static ERROR_AUCTION_FINALIZED: &[u8] = b"auction already finalized";
+pub static ERROR_AUCTION_NOT_FINALIZED: &[u8] = b"auction not finalized";
+pub static ERROR_WRONG_REGISTRATION_END_TIME: &[u8] = b"registration must end before the launchpad starts";
+pub static ERROR_ZERO_WINNERS: &[u8] = b"winners count can not be zero";
+pub static ERROR_REGISTRATION_ENDED: &[u8] = b"lottery registration ended";
+pub static ERROR_REGISTRATION_NOT_ENDED: &[u8] = b"lottery registration not ended";
+pub static ERROR_ALREADY_REGISTERED: &[u8] = b"already registered";
+pub static ERROR_NO_TICKETS: &[u8] = b"payment too low for a ticket";
+pub static ERROR_LOTTERY_DRAWN: &[u8] = b"lottery already drawn";
+pub static ERROR_LOTTERY_NOT_DRAWN: &[u8] = b"lottery not drawn";
+pub static ERROR_NOT_WINNER: &[u8] = b"user not a lottery winner";
+pub static ERROR_ALREADY_SETTLED: &[u8] = b"already settled";
+pub static ERROR_NOTHING_TO_SETTLE: &[u8] = b"nothing to settle";
 pub static ERROR_LAUNCHPAD_NOT_ENDED: &[u8] = b"launchpad end time not reached";
 pub static ERROR_ALREADY_DEPLOYED: &[u8] = b"franchise already deployed";
 pub static ERROR_DELETING_LAUNCHPAD: &[u8] = b"can not delete a launchpad when tokens were sold";
+pub static ERROR_SOFT_CAP_NOT_REACHED: &[u8] = b"soft cap not reached";
+pub static ERROR_LAUNCHPAD_NOT_FAILED: &[u8] = b"launchpad did not fail and was not cancelled";
+pub static ERROR_LAUNCHPAD_CANCELLED: &[u8] = b"launchpad cancelled";
+pub static ERROR_NOTHING_TO_REFUND: &[u8] = b"nothing to refund";
+pub static ERROR_NOT_ESCROW: &[u8] = b"launchpad tokens are not escrowed";
+pub static ERROR_CLAIM_NOT_OPEN: &[u8] = b"claim period not started";
+pub static ERROR_WRONG_VESTING: &[u8] = b"invalid vesting schedule";
+pub static ERROR_VESTING_REQUIRES_ESCROW: &[u8] = b"vesting requires escrowed tokens";
+pub static ERROR_VESTED_LAUNCHPAD: &[u8] = b"launchpad tokens are vested, use claimVested";
+pub static ERROR_NOT_VESTED: &[u8] = b"launchpad tokens are not vested";
+pub static ERROR_NOTHING_TO_CLAIM: &[u8] = b"nothing to claim";
+pub static ERROR_WRONG_RETURNED_AMOUNT: &[u8] = b"must return all bought tokens";
+pub static ERROR_PAYMENT_TOKEN_NOT_FOUND: &[u8] = b"payment token not found";
+pub static ERROR_MINTED_LAUNCHPAD: &[u8] = b"launchpad tokens are minted on buy";
+pub static ERROR_MISSING_MINT_ROLES: &[u8] = b"launchpad contract needs the local mint and burn roles";
+pub static ERROR_TOKEN_ALREADY_SET: &[u8] = b"launchpad token already set";
+pub static ERROR_TOKEN_NOT_SET: &[u8] = b"launchpad token not issued yet";
+pub static ERROR_WRONG_DECIMALS: &[u8] = b"wrong number of decimals";
+pub static ERROR_UNKNOWN_DECIMALS: &[u8] = b"token decimals unknown, call loadTokenDecimals first";
+pub static ERROR_SWAP_FAILED: &[u8] = b"swap returned no payment tokens";
+pub static ERROR_ROUNDS_WITH_PAYMENT_TOKENS: &[u8] = b"rounds can not be combined with multiple payment tokens";
+pub static ERROR_WRONG_PAGE_SIZE: &[u8] = b"wrong page size";
 pub static ERROR_ONLY_OWNER_OR_DAO: &[u8] = b"only the owner or the main DAO can execute this action";
 pub static ERROR_DAO_ALREADY_SET: &[u8] = b"DAO contract address already set";
 pub static ERROR_DAO_NOT_SET: &[u8] = b"DAO contract address not set";
@@ -23,3 +79,10 @@ pub static ERROR_DEX_ALREADY_SET: &[u8] = b"DEX contract address already set";
 pub static ERROR_DEX_NOT_SET: &[u8] = b"DEX contract address not set";
 pub static ERROR_DIGITAL_IDENTITY_ALREADY_SET: &[u8] = b"digital identity contract address already set";
 pub static ERROR_DIGITAL_IDENTITY_NOT_SET: &[u8] = b"digital identity contract address not set";
+pub static ERROR_SC_CALLER: &[u8] = b"smart contracts cannot execute this action";
+pub static ERROR_TOO_MANY_BIDS: &[u8] = b"maximum number of bidders reached";
+pub static ERROR_EGLD_NOT_ACCEPTED: &[u8] = b"EGLD payments are not accepted";
+pub static ERROR_NOT_DEPLOYED: &[u8] = b"franchise not deployed yet";
+pub static ERROR_TOKEN_NOT_ISSUED: &[u8] = b"launchpad token was not issued by the launchpad";
+pub static ERROR_TOKEN_RELEASED: &[u8] = b"launchpad token already released";
+pub static ERROR_OLD_STORAGE: &[u8] = b"launchpads stored by an older version cannot be decoded, redeploy the contract";
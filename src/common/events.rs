@@ -0,0 +1,76 @@
+multiversx_sc::imports!();
+
+use crate::common::config::{Launchpad, State};
+
+#[multiversx_sc::module]
+pub trait EventsModule {
+    #[event("state_change")]
+    fn state_change_event(&self, #[indexed] state: State);
+
+    #[event("new_launchpad")]
+    fn new_launchpad_event(
+        &self,
+        #[indexed] id: u64,
+        #[indexed] owner: &ManagedAddress,
+        #[indexed] token: &TokenIdentifier,
+        launchpad: &Launchpad<Self::Api>
+    );
+
+    #[event("update_launchpad")]
+    fn update_launchpad_event(&self, #[indexed] id: u64, launchpad: &Launchpad<Self::Api>);
+
+    #[event("add_tokens")]
+    fn add_tokens_event(&self, #[indexed] id: u64, #[indexed] token: &TokenIdentifier, amount: &BigUint);
+
+    #[event("remove_tokens")]
+    fn remove_tokens_event(&self, #[indexed] id: u64, #[indexed] token: &TokenIdentifier, amount: &BigUint);
+
+    #[event("whitelist_user")]
+    fn whitelist_user_event(&self, #[indexed] id: u64, #[indexed] user: &ManagedAddress);
+
+    #[event("whitelist_round_user")]
+    fn whitelist_round_user_event(&self, #[indexed] id: u64, #[indexed] round_id: usize, #[indexed] user: &ManagedAddress);
+
+    #[event("buy")]
+    fn buy_event(
+        &self,
+        #[indexed] id: u64,
+        #[indexed] buyer: &ManagedAddress,
+        #[indexed] payment_token: &EgldOrEsdtTokenIdentifier,
+        #[indexed] paid: &BigUint,
+        tokens: &BigUint
+    );
+
+    // pro-rata and fair launch commitments, batch auction deposits
+    #[event("commit")]
+    fn commit_event(
+        &self,
+        #[indexed] id: u64,
+        #[indexed] user: &ManagedAddress,
+        #[indexed] payment_token: &EgldOrEsdtTokenIdentifier,
+        amount: &BigUint
+    );
+
+    #[event("settle")]
+    fn settle_event(&self, #[indexed] id: u64, #[indexed] user: &ManagedAddress, #[indexed] tokens: &BigUint, refund: &BigUint);
+
+    #[event("claim")]
+    fn claim_event(&self, #[indexed] id: u64, #[indexed] user: &ManagedAddress, amount: &BigUint);
+
+    #[event("refund")]
+    fn refund_event(&self, #[indexed] id: u64, #[indexed] user: &ManagedAddress, returned_tokens: &BigUint);
+
+    #[event("cancel_launchpad")]
+    fn cancel_launchpad_event(&self, #[indexed] id: u64, #[indexed] forced: bool);
+
+    #[event("deploy_franchise")]
+    fn deploy_franchise_event(
+        &self,
+        #[indexed] id: u64,
+        #[indexed] franchise_address: &ManagedAddress,
+        #[indexed] identity_id: u64
+    );
+
+    #[event("upgrade_franchise")]
+    fn upgrade_franchise_event(&self, #[indexed] franchise_address: &ManagedAddress);
+}
@@ -14,14 +14,24 @@ use tfn_digital_identity::common::config::Identity;
 #[multiversx_sc::contract]
 pub trait TFNLaunchpadContract<ContractReader>:
     common::config::ConfigModule
+    + common::events::EventsModule
 {
     #[init]
     fn init(&self) {
+        self.storage_version().set(STORAGE_VERSION);
     }
 
+    // the launchpad encoding is not migrated: contracts that already stored launchpads with an older
+    // encoding cannot decode them (not even in clearStorage) and must be redeployed
     #[upgrade]
     fn upgrade(&self) {
         // self.clear_storage();
+        require!(
+            self.last_launchpad_id().get() == 0 || self.storage_version().get() == STORAGE_VERSION,
+            ERROR_OLD_STORAGE
+        );
+
+        self.storage_version().set(STORAGE_VERSION);
     }
 
     // DEBUG ENDPOINT
@@ -35,12 +45,31 @@ pub trait TFNLaunchpadContract<ContractReader>:
                 self.whitelisted_users(launchpad_id).clear();
                 for user in self.launchpad_users(launchpad_id).iter() {
                     self.user_participation(&user, launchpad_id).clear();
+                    self.user_payment(&user, launchpad_id).clear();
+                    self.user_claimed(&user, launchpad_id).clear();
+                    self.user_settled(&user, launchpad_id).clear();
+                    self.bid_hashes(launchpad_id, &user).clear();
+                    for round_id in 1..=self.rounds(launchpad_id).len() {
+                        self.user_round_participation(&user, launchpad_id, round_id).clear();
+                    }
+                    for token in self.payment_token_prices(launchpad_id).keys() {
+                        self.user_token_payment(&user, launchpad_id, &token).clear();
+                    }
                     self.user_launchpads(&user).clear();
+                    self.user_purchases(&user).clear();
                 }
+                self.purchases(launchpad_id).clear();
+                self.franchise_address(launchpad_id).clear();
                 self.launchpad_users(launchpad_id).clear();
+                self.token_issued(launchpad_id).clear();
+                self.token_released(launchpad_id).clear();
+                self.minted_tokens(launchpad_id).clear();
+                self.unindex_launchpad(&launchpad);
+                self.clear_sale_config(launchpad_id);
                 self.launchpads(launchpad_id).clear();
             }
         }
+        self.total_raised_by_token().clear();
         // clear deployed_launchpads ?
         self.set_state_inactive();
     }
@@ -51,51 +80,283 @@ pub trait TFNLaunchpadContract<ContractReader>:
         owner: ManagedAddress,
         details: Identity<Self::Api>,
         kyc_enforced: bool,
-        token: TokenIdentifier,
-        payment_token: TokenIdentifier,
-        price: BigUint, // if payment token is USDC (6 decimals), price should be x_000_000
+        sale_type: SaleType,
+        token: Option<TokenIdentifier>, // if None, the token is issued later with issueLaunchpadToken
+        payment_token: EgldOrEsdtTokenIdentifier,
+        price: BigUint, // payment token units for one whole sale token, e.g. x_000_000 for 1 token = x USDC
         min_buy_amount: BigUint,
         max_buy_amount: BigUint,
+        soft_cap: BigUint,
         start_time: u64,
-        end_time: u64
+        end_time: u64,
+        escrow: bool,
+        claim_start: u64,
+        vesting: Option<VestingSchedule>,
+        mint_cap: Option<BigUint>
     ) -> u64 {
         require!(self.state().get() == State::Active, ERROR_NOT_ACTIVE);
         self.only_dao();
 
-        require!(price > 0, ERROR_ZERO_PRICE);
-        require!(min_buy_amount <= max_buy_amount, ERROR_WRONG_MIN_MAX_AMOUNTS);
+        if let Some(mint_cap) = &mint_cap {
+            require!(*mint_cap > 0, ERROR_ZERO_AMOUNT);
+        }
 
-        let now = self.blockchain().get_block_timestamp();
-        require!(now < start_time, ERROR_WRONG_START_TIME);
-        require!(start_time < end_time, ERROR_WRONG_END_TIME);
+        let (token, token_decimals) = match token {
+            Some(token) => {
+                require!(!self.launched_tokens().contains(&token), ERROR_TOKEN_ALREADY_LAUNCHED);
 
-        require!(self.token_launchpad_id(&token).is_empty(), ERROR_TOKEN_ALREADY_LAUNCHED);
+                let decimals = self.get_token_decimals(&EgldOrEsdtTokenIdentifier::esdt(token.clone()));
+                (token, decimals)
+            },
+            None => (TokenIdentifier::from(ManagedBuffer::new()), 0),
+        };
+        let payment_decimals = self.get_token_decimals(&payment_token);
 
         let launchpad = Launchpad{
             id: self.last_launchpad_id().get(),
             owner,
             details,
             kyc_enforced,
+            sale_type,
             token: token.clone(),
-            amount: BigUint::zero(),
+            token_decimals,
+            amount: mint_cap.clone().unwrap_or_default(),
+            mint_cap,
             payment_token,
+            payment_decimals,
             price,
             min_buy_amount,
             max_buy_amount,
+            soft_cap,
             start_time,
             end_time,
+            escrow,
+            claim_start,
+            vesting,
             total_raised: BigUint::zero(),
             total_sold: BigUint::zero(),
+            clearing_price: BigUint::zero(),
             deployed: false,
+            cancelled: false,
             status: Status::Pending,
         };
+        self.validate_launchpad(&launchpad);
         self.launchpads(launchpad.id).set(&launchpad);
-        self.token_launchpad_id(&token).set(launchpad.id);
+        if launchpad.has_token() {
+            self.token_launchpad_id(&token).set(launchpad.id);
+        }
         self.last_launchpad_id().set(launchpad.id + 1);
+        self.index_launchpad(&launchpad);
+        self.new_launchpad_event(launchpad.id, &launchpad.owner, &launchpad.token, &launchpad);
 
         launchpad.id
     }
 
+    // the sale configuration (rounds, auctions, lottery) must still fit the new schedule and price
+    #[endpoint(updateLaunchpad)]
+    fn update_launchpad(
+        &self,
+        id: u64,
+        details: Identity<Self::Api>,
+        kyc_enforced: bool,
+        price: BigUint,
+        min_buy_amount: BigUint,
+        max_buy_amount: BigUint,
+        start_time: u64,
+        end_time: u64
+    ) {
+        require!(self.state().get() == State::Active, ERROR_NOT_ACTIVE);
+        self.only_dao_or_launchpad_owner(id);
+
+        let mut launchpad = self.launchpads(id).get();
+        require!(!launchpad.cancelled, ERROR_LAUNCHPAD_CANCELLED);
+        require!(self.blockchain().get_block_timestamp() < launchpad.start_time, ERROR_LAUNCHPAD_STARTED);
+
+        launchpad.details = details;
+        launchpad.kyc_enforced = kyc_enforced;
+        launchpad.price = price;
+        launchpad.min_buy_amount = min_buy_amount;
+        launchpad.max_buy_amount = max_buy_amount;
+        launchpad.start_time = start_time;
+        launchpad.end_time = end_time;
+        self.validate_launchpad(&launchpad);
+        self.validate_sale_config(&launchpad);
+
+        self.update_launchpad_event(id, &launchpad);
+        self.launchpads(id).set(launchpad);
+    }
+
+    fn validate_launchpad(&self, launchpad: &Launchpad<Self::Api>) {
+        require!(launchpad.price > 0 || launchpad.sale_type == SaleType::FairLaunch, ERROR_ZERO_PRICE);
+        require!(launchpad.min_buy_amount <= launchpad.max_buy_amount, ERROR_WRONG_MIN_MAX_AMOUNTS);
+
+        let now = self.blockchain().get_block_timestamp();
+        require!(now < launchpad.start_time, ERROR_WRONG_START_TIME);
+        require!(launchpad.start_time < launchpad.end_time, ERROR_WRONG_END_TIME);
+        require!(
+            launchpad.claim_start == 0 || launchpad.claim_start >= launchpad.end_time,
+            ERROR_WRONG_CLAIM_START
+        );
+        if let Some(vesting) = &launchpad.vesting {
+            require!(launchpad.escrow, ERROR_VESTING_REQUIRES_ESCROW);
+            require!(vesting.is_valid(), ERROR_WRONG_VESTING);
+        }
+    }
+
+    fn validate_sale_config(&self, launchpad: &Launchpad<Self::Api>) {
+        let rounds_count = self.rounds(launchpad.id).len();
+        if rounds_count > 0 {
+            require!(
+                self.rounds(launchpad.id).get(1).start_time >= launchpad.start_time &&
+                    self.rounds(launchpad.id).get(rounds_count).end_time <= launchpad.end_time,
+                ERROR_WRONG_ROUND_TIME
+            );
+        }
+        if !self.dutch_auctions(launchpad.id).is_empty() {
+            let auction = self.dutch_auctions(launchpad.id).get();
+            require!(auction.start_price > launchpad.price, ERROR_WRONG_START_PRICE);
+            require!(
+                auction.decay == PriceDecay::Linear || auction.step_duration <= launchpad.end_time - launchpad.start_time,
+                ERROR_WRONG_STEP_DURATION
+            );
+        }
+        if !self.bonding_curves(launchpad.id).is_empty() {
+            require!(self.bonding_curves(launchpad.id).get().fits_amount(&launchpad.amount), ERROR_WRONG_CURVE);
+        }
+        if !self.batch_auctions(launchpad.id).is_empty() {
+            let commit_end_time = self.batch_auctions(launchpad.id).get().commit_end_time;
+            require!(
+                launchpad.start_time < commit_end_time && commit_end_time < launchpad.end_time,
+                ERROR_WRONG_COMMIT_END_TIME
+            );
+        }
+        if !self.lotteries(launchpad.id).is_empty() {
+            require!(
+                self.lotteries(launchpad.id).get().registration_end_time <= launchpad.start_time,
+                ERROR_WRONG_REGISTRATION_END_TIME
+            );
+        }
+    }
+
+    // issues the sale token of a launchpad created without one. the contract gets all the token roles
+    // and the initial supply is deposited for sale. the token is handed over to the franchise DAO
+    // with releaseLaunchpadToken after deploy
+    #[payable("EGLD")]
+    #[endpoint(issueLaunchpadToken)]
+    fn issue_launchpad_token(
+        &self,
+        id: u64,
+        name: ManagedBuffer,
+        ticker: ManagedBuffer,
+        decimals: u32,
+        supply: BigUint
+    ) {
+        require!(self.state().get() == State::Active, ERROR_NOT_ACTIVE);
+        self.only_dao_or_launchpad_owner(id);
+
+        let launchpad = self.launchpads(id).get();
+        require!(!launchpad.has_token() && !self.token_issued(id).get(), ERROR_TOKEN_ALREADY_SET);
+        require!(self.blockchain().get_block_timestamp() < launchpad.start_time, ERROR_LAUNCHPAD_STARTED);
+        require!(decimals <= MAX_DECIMALS, ERROR_WRONG_DECIMALS);
+        require!(supply == 0 || !launchpad.mints_on_buy(), ERROR_MINTED_LAUNCHPAD);
+        if !self.bonding_curves(id).is_empty() {
+            require!(self.bonding_curves(id).get().fits_amount(&(&launchpad.amount + &supply)), ERROR_WRONG_CURVE);
+        }
+
+        self.token_issued(id).set(true);
+        let issue_cost = self.call_value().egld_value().clone_value();
+        self.tx()
+            .to(ESDTSystemSCAddress)
+            .typed(system_proxy::ESDTSystemSCProxy)
+            .issue_and_set_all_roles(issue_cost, name, ticker, EsdtTokenType::Fungible, decimals as usize)
+            .callback(self.callbacks().issue_launchpad_token_callback(
+                id,
+                self.blockchain().get_caller(),
+                decimals,
+                supply
+            ))
+            .async_call_and_exit();
+    }
+
+    #[callback]
+    fn issue_launchpad_token_callback(
+        &self,
+        id: u64,
+        caller: ManagedAddress,
+        decimals: u32,
+        supply: BigUint,
+        #[call_result] result: ManagedAsyncCallResult<TokenIdentifier>
+    ) {
+        match result {
+            ManagedAsyncCallResult::Ok(token) => {
+                self.token_decimals().insert(token.clone(), decimals);
+                // the launchpad may have been cancelled in the meantime
+                if self.launchpads(id).is_empty() || !self.token_issued(id).get() {
+                    return
+                }
+
+                let mut launchpad = self.launchpads(id).get();
+                if supply > 0 {
+                    self.send().esdt_local_mint(&token, 0, &supply);
+                    launchpad.amount += supply;
+                }
+                launchpad.token = token.clone();
+                launchpad.token_decimals = decimals;
+                self.launchpads(id).set(launchpad);
+                self.token_launchpad_id(&token).set(id);
+                self.launched_tokens().insert(token);
+            },
+            ManagedAsyncCallResult::Err(_) => {
+                self.token_issued(id).clear();
+                let refund = self.call_value().egld_value().clone_value();
+                if refund > 0 {
+                    self.send().direct_egld(&caller, &refund);
+                }
+            },
+        }
+    }
+
+    // reads the token decimals from the ESDT system SC. must be called for the sale and payment tokens
+    // before creating a launchpad
+    #[endpoint(loadTokenDecimals)]
+    fn load_token_decimals(&self, token: TokenIdentifier) {
+        require!(token.is_valid_esdt_identifier(), ERROR_WRONG_TOKEN);
+
+        self.tx()
+            .to(ESDTSystemSCAddress)
+            .raw_call("getTokenProperties")
+            .argument(&token)
+            .callback(self.callbacks().token_properties_callback(token))
+            .async_call_and_exit();
+    }
+
+    // tokens whose decimals can not be determined are not stored and can not be used in launchpads
+    #[callback]
+    fn token_properties_callback(
+        &self,
+        token: TokenIdentifier,
+        #[call_result] result: ManagedAsyncCallResult<MultiValueEncoded<ManagedBuffer>>
+    ) {
+        let properties = match result {
+            ManagedAsyncCallResult::Ok(properties) => properties,
+            ManagedAsyncCallResult::Err(_) => return,
+        };
+
+        for property in properties.into_iter() {
+            if property.len() > MAX_TOKEN_PROPERTY_LENGTH {
+                continue
+            }
+
+            let mut buffer = [0u8; MAX_TOKEN_PROPERTY_LENGTH];
+            if let Some(decimals) = parse_num_decimals(property.load_to_byte_array(&mut buffer)) {
+                if decimals <= MAX_DECIMALS {
+                    self.token_decimals().insert(token, decimals);
+                }
+                return
+            }
+        }
+    }
+
     #[payable("*")]
     #[endpoint(addTokens)]
     fn add_tokens(&self, id: u64) {
@@ -103,12 +364,51 @@ pub trait TFNLaunchpadContract<ContractReader>:
         require!(!self.launchpads(id).is_empty(), ERROR_LAUNCHPAD_NOT_FOUND);
 
         let mut launchpad = self.launchpads(id).get();
-        require!(launchpad.end_time > self.blockchain().get_block_timestamp(), ERROR_LAUNCHPAD_INACTIVE);
+        let now = self.blockchain().get_block_timestamp();
+        require!(launchpad.end_time > now, ERROR_LAUNCHPAD_INACTIVE);
+        require!(!launchpad.cancelled, ERROR_LAUNCHPAD_CANCELLED);
+        require!(!launchpad.mints_on_buy(), ERROR_MINTED_LAUNCHPAD);
+        // a sold out dutch auction already fixed its clearing price
+        require!(
+            launchpad.sale_type != SaleType::DutchAuction || now < launchpad.start_time,
+            ERROR_LAUNCHPAD_STARTED
+        );
 
         let payment = self.call_value().single_esdt();
         require!(launchpad.token == payment.token_identifier, ERROR_WRONG_TOKEN);
 
-        launchpad.amount += payment.amount;
+        launchpad.amount += &payment.amount;
+        self.validate_sale_config(&launchpad);
+        self.add_tokens_event(id, &payment.token_identifier, &payment.amount);
+        self.launchpads(id).set(launchpad);
+    }
+
+    // before the sale starts any unsold amount can be removed. escrowed sales that sell on buy
+    // keep the sold tokens in the contract, so their unsold amount can be removed until deploy
+    #[endpoint(removeTokens)]
+    fn remove_tokens(&self, id: u64, amount: BigUint) {
+        require!(self.state().get() == State::Active, ERROR_NOT_ACTIVE);
+        self.only_launchpad_owner(id);
+
+        let mut launchpad = self.launchpads(id).get();
+        require!(!launchpad.cancelled, ERROR_LAUNCHPAD_CANCELLED);
+        require!(!launchpad.mints_on_buy(), ERROR_MINTED_LAUNCHPAD);
+        require!(
+            self.blockchain().get_block_timestamp() < launchpad.start_time ||
+                (launchpad.escrow && !launchpad.needs_settlement() && !launchpad.deployed),
+            ERROR_LAUNCHPAD_STARTED
+        );
+        require!(amount > 0, ERROR_ZERO_AMOUNT);
+        require!(amount <= &launchpad.amount - &launchpad.total_sold, ERROR_INSUFFICIENT_FUNDS);
+
+        self.send().direct_esdt(
+            &launchpad.owner,
+            &launchpad.token,
+            0,
+            &amount
+        );
+        self.remove_tokens_event(id, &launchpad.token, &amount);
+        launchpad.amount -= amount;
         self.launchpads(id).set(launchpad);
     }
 
@@ -118,13 +418,22 @@ pub trait TFNLaunchpadContract<ContractReader>:
         self.only_launchpad_owner(id);
 
         let launchpad = self.launchpads(id).get();
-        require!(launchpad.total_sold == 0, ERROR_DELETING_LAUNCHPAD);
+        require!(launchpad.total_sold == 0 && launchpad.total_raised == 0, ERROR_DELETING_LAUNCHPAD);
+        require!(self.lottery_entries(id).is_empty(), ERROR_DELETING_LAUNCHPAD);
+        // refunded launchpads keep their users and purchase history, so their id is never reused
+        require!(
+            self.launchpad_users(id).is_empty() && self.purchases(id).is_empty(),
+            ERROR_DELETING_LAUNCHPAD
+        );
 
         self.launchpads(id).clear();
         self.token_launchpad_id(&launchpad.token).clear();
+        self.token_issued(id).clear();
         self.whitelisted_users(id).clear();
+        self.unindex_launchpad(&launchpad);
+        self.clear_sale_config(id);
 
-        if launchpad.amount > 0 {
+        if launchpad.amount > 0 && !launchpad.mints_on_buy() {
             self.send().direct_esdt(
                 &launchpad.owner,
                 &launchpad.token,
@@ -135,6 +444,48 @@ pub trait TFNLaunchpadContract<ContractReader>:
         if self.last_launchpad_id().get() ==  id + 1 {
             self.last_launchpad_id().set(id);
         }
+        self.cancel_launchpad_event(id, false);
+    }
+
+    // freezes a launchpad that already sold tokens. buyers get their payment back with claimRefund
+    // (returning the tokens they received) and the remaining deposit goes back to the owner
+    #[endpoint(forceCancelLaunchpad)]
+    fn force_cancel_launchpad(&self, id: u64) {
+        require!(self.state().get() == State::Active, ERROR_NOT_ACTIVE);
+        self.only_dao();
+        require!(!self.launchpads(id).is_empty(), ERROR_LAUNCHPAD_NOT_FOUND);
+
+        let mut launchpad = self.launchpads(id).get();
+        require!(!launchpad.deployed, ERROR_ALREADY_DEPLOYED);
+        require!(!launchpad.cancelled, ERROR_LAUNCHPAD_CANCELLED);
+
+        let unsold_amount = &launchpad.amount - &launchpad.total_sold;
+        if unsold_amount > 0 && !launchpad.mints_on_buy() {
+            self.send().direct_esdt(
+                &launchpad.owner,
+                &launchpad.token,
+                0,
+                &unsold_amount
+            );
+            launchpad.amount -= unsold_amount;
+        }
+        launchpad.cancelled = true;
+        self.launchpads(id).set(launchpad);
+        self.set_status_index(id, Status::Cancelled);
+        self.cancel_launchpad_event(id, true);
+    }
+
+    // moves the given launchpads to the index of their current status. anyone can call it,
+    // since launchpads end or fail by time without any transaction
+    #[endpoint(updateStatusIndexes)]
+    fn update_status_indexes(&self, ids: MultiValueEncoded<u64>) {
+        require!(self.state().get() == State::Active, ERROR_NOT_ACTIVE);
+
+        for id in ids {
+            if !self.launchpads(id).is_empty() {
+                self.refresh_status_index(&self.launchpads(id).get());
+            }
+        }
     }
 
     #[endpoint(whitelistUser)]
@@ -142,55 +493,850 @@ pub trait TFNLaunchpadContract<ContractReader>:
         require!(self.state().get() == State::Active, ERROR_NOT_ACTIVE);
         self.only_launchpad_owner(id);
 
+        self.whitelist_user_event(id, &user);
         self.whitelisted_users(id).insert(user);
     }
 
+    // rounds must be added in chronological order, before the launchpad starts
+    #[endpoint(addRound)]
+    fn add_round(
+        &self,
+        id: u64,
+        price: BigUint,
+        start_time: u64,
+        end_time: u64,
+        min_buy_amount: BigUint,
+        max_buy_amount: BigUint,
+        allocation: BigUint,
+        whitelist_enforced: bool
+    ) -> usize {
+        require!(self.state().get() == State::Active, ERROR_NOT_ACTIVE);
+        self.only_launchpad_owner(id);
+
+        let launchpad = self.launchpads(id).get();
+        require!(launchpad.sale_type == SaleType::FixedPrice, ERROR_WRONG_SALE_TYPE);
+        require!(self.blockchain().get_block_timestamp() < launchpad.start_time, ERROR_LAUNCHPAD_STARTED);
+        require!(price > 0, ERROR_ZERO_PRICE);
+        require!(min_buy_amount <= max_buy_amount, ERROR_WRONG_MIN_MAX_AMOUNTS);
+        require!(start_time < end_time, ERROR_WRONG_END_TIME);
+        require!(
+            start_time >= launchpad.start_time && end_time <= launchpad.end_time,
+            ERROR_WRONG_ROUND_TIME
+        );
+
+        require!(self.payment_token_prices(id).is_empty(), ERROR_ROUNDS_WITH_PAYMENT_TOKENS);
+
+        let rounds_count = self.rounds(id).len();
+        if rounds_count > 0 {
+            require!(self.rounds(id).get(rounds_count).end_time < start_time, ERROR_OVERLAPPING_ROUNDS);
+        }
+
+        self.rounds(id).push(&Round {
+            price,
+            start_time,
+            end_time,
+            min_buy_amount,
+            max_buy_amount,
+            allocation,
+            whitelist_enforced,
+            total_raised: BigUint::zero(),
+            total_sold: BigUint::zero(),
+        })
+    }
+
+    #[endpoint(whitelistRoundUser)]
+    fn whitelist_round_user(&self, id: u64, round_id: usize, user: ManagedAddress) {
+        require!(self.state().get() == State::Active, ERROR_NOT_ACTIVE);
+        self.only_launchpad_owner(id);
+        require!(round_id > 0 && round_id <= self.rounds(id).len(), ERROR_ROUND_NOT_FOUND);
+
+        self.whitelist_round_user_event(id, round_id, &user);
+        self.round_whitelisted_users(id, round_id).insert(user);
+    }
+
+    // extra payment tokens can be accepted by fixed price sales without rounds
+    #[endpoint(addPaymentToken)]
+    fn add_payment_token(&self, id: u64, token: EgldOrEsdtTokenIdentifier, price: BigUint) {
+        require!(self.state().get() == State::Active, ERROR_NOT_ACTIVE);
+        self.only_launchpad_owner(id);
+
+        let launchpad = self.launchpads(id).get();
+        require!(
+            launchpad.sale_type == SaleType::FixedPrice || launchpad.sale_type == SaleType::Lottery,
+            ERROR_WRONG_SALE_TYPE
+        );
+        require!(self.blockchain().get_block_timestamp() < launchpad.start_time, ERROR_LAUNCHPAD_STARTED);
+        require!(self.rounds(id).is_empty(), ERROR_ROUNDS_WITH_PAYMENT_TOKENS);
+        require!(token.is_valid() && token != launchpad.payment_token, ERROR_WRONG_TOKEN);
+        require!(price > 0, ERROR_ZERO_PRICE);
+        self.get_token_decimals(&token); // fails if the decimals are unknown
+
+        self.payment_token_launchpads(&token).insert(id);
+        self.payment_token_prices(id).insert(token, price);
+    }
+
+    #[endpoint(removePaymentToken)]
+    fn remove_payment_token(&self, id: u64, token: EgldOrEsdtTokenIdentifier) {
+        require!(self.state().get() == State::Active, ERROR_NOT_ACTIVE);
+        self.only_launchpad_owner(id);
+
+        let launchpad = self.launchpads(id).get();
+        require!(self.blockchain().get_block_timestamp() < launchpad.start_time, ERROR_LAUNCHPAD_STARTED);
+        require!(self.payment_token_prices(id).remove(&token).is_some(), ERROR_PAYMENT_TOKEN_NOT_FOUND);
+        self.payment_token_launchpads(&token).swap_remove(&id);
+    }
+
+    fn clear_sale_config(&self, id: u64) {
+        for round_id in 1..=self.rounds(id).len() {
+            self.round_whitelisted_users(id, round_id).clear();
+        }
+        self.rounds(id).clear();
+        self.dutch_auctions(id).clear();
+        self.bonding_curves(id).clear();
+        self.batch_auctions(id).clear();
+        self.revealed_bids(id).clear();
+        for entry in self.lottery_entries(id).iter() {
+            self.lottery_entry_index(id, &entry.user).clear();
+            self.lottery_stakes(id, &entry.user).clear();
+        }
+        self.lottery_entries(id).clear();
+        self.lottery_total_tickets(id).clear();
+        self.lottery_winners(id).clear();
+        self.lotteries(id).clear();
+        for token in self.payment_token_prices(id).keys() {
+            self.token_raised(id, &token).clear();
+        }
+        self.payment_token_prices(id).clear();
+    }
+
+    // the launchpad price is used as floor price
+    #[endpoint(setDutchAuction)]
+    fn set_dutch_auction(&self, id: u64, start_price: BigUint, decay: PriceDecay, step_duration: u64) {
+        require!(self.state().get() == State::Active, ERROR_NOT_ACTIVE);
+        self.only_dao_or_launchpad_owner(id);
+
+        let launchpad = self.launchpads(id).get();
+        require!(launchpad.sale_type == SaleType::DutchAuction, ERROR_WRONG_SALE_TYPE);
+        require!(self.blockchain().get_block_timestamp() < launchpad.start_time, ERROR_LAUNCHPAD_STARTED);
+        require!(start_price > launchpad.price, ERROR_WRONG_START_PRICE);
+        require!(
+            decay == PriceDecay::Linear ||
+                (step_duration > 0 && step_duration <= launchpad.end_time - launchpad.start_time),
+            ERROR_WRONG_STEP_DURATION
+        );
+
+        self.dutch_auctions(id).set(DutchAuction {
+            start_price,
+            decay,
+            step_duration,
+        });
+    }
+
+    // the launchpad price is used as the price of the first token
+    #[endpoint(setBondingCurve)]
+    fn set_bonding_curve(&self, id: u64, curve_type: CurveType, slope: BigUint, growth: u64, step_size: BigUint) {
+        require!(self.state().get() == State::Active, ERROR_NOT_ACTIVE);
+        self.only_dao_or_launchpad_owner(id);
+
+        let launchpad = self.launchpads(id).get();
+        require!(launchpad.sale_type == SaleType::BondingCurve, ERROR_WRONG_SALE_TYPE);
+        require!(self.blockchain().get_block_timestamp() < launchpad.start_time, ERROR_LAUNCHPAD_STARTED);
+
+        let curve = BondingCurve {
+            curve_type,
+            slope,
+            growth,
+            step_size,
+        };
+        require!(curve.is_valid() && curve.fits_amount(&launchpad.amount), ERROR_WRONG_CURVE);
+
+        self.bonding_curves(id).set(curve);
+    }
+
+    // the launchpad price is used as reserve price
+    #[endpoint(setBatchAuction)]
+    fn set_batch_auction(&self, id: u64, commit_end_time: u64) {
+        require!(self.state().get() == State::Active, ERROR_NOT_ACTIVE);
+        self.only_dao_or_launchpad_owner(id);
+
+        let launchpad = self.launchpads(id).get();
+        require!(launchpad.sale_type == SaleType::BatchAuction, ERROR_WRONG_SALE_TYPE);
+        require!(self.blockchain().get_block_timestamp() < launchpad.start_time, ERROR_LAUNCHPAD_STARTED);
+        require!(
+            launchpad.start_time < commit_end_time && commit_end_time < launchpad.end_time,
+            ERROR_WRONG_COMMIT_END_TIME
+        );
+
+        self.batch_auctions(id).set(BatchAuction {
+            commit_end_time,
+        });
+    }
+
+    // registration ends before the launchpad starts. the launchpad max buy amount is the allocation of each winner
+    #[endpoint(setLottery)]
+    fn set_lottery(&self, id: u64, registration_end_time: u64, winners_count: usize, tokens_per_ticket: BigUint) {
+        require!(self.state().get() == State::Active, ERROR_NOT_ACTIVE);
+        self.only_dao_or_launchpad_owner(id);
+
+        let launchpad = self.launchpads(id).get();
+        require!(launchpad.sale_type == SaleType::Lottery, ERROR_WRONG_SALE_TYPE);
+        require!(self.lottery_entries(id).is_empty(), ERROR_REGISTRATION_ENDED);
+
+        let now = self.blockchain().get_block_timestamp();
+        require!(
+            now < registration_end_time && registration_end_time <= launchpad.start_time,
+            ERROR_WRONG_REGISTRATION_END_TIME
+        );
+        require!(winners_count > 0, ERROR_ZERO_WINNERS);
+
+        self.lotteries(id).set(Lottery {
+            registration_end_time,
+            winners_count,
+            tokens_per_ticket,
+        });
+    }
+
+    // in weighted lotteries the caller locks governance tokens for tickets until the draw
     #[payable("*")]
-    #[endpoint(buy)]
-    fn buy(&self, id: u64) {
+    #[endpoint(registerLottery)]
+    fn register_lottery(&self, id: u64) {
         require!(self.state().get() == State::Active, ERROR_NOT_ACTIVE);
         require!(!self.launchpads(id).is_empty(), ERROR_LAUNCHPAD_NOT_FOUND);
 
-        let mut launchpad = self.launchpads(id).get();
-        require!(launchpad.is_active(self.blockchain().get_block_timestamp()), ERROR_LAUNCHPAD_INACTIVE);
+        let launchpad = self.launchpads(id).get();
+        require!(launchpad.sale_type == SaleType::Lottery, ERROR_WRONG_SALE_TYPE);
 
-        let payment = self.call_value().single_esdt();
-        require!(payment.token_identifier == launchpad.payment_token, ERROR_WRONG_TOKEN);
+        let lottery = self.get_lottery(id);
+        require!(self.blockchain().get_block_timestamp() < lottery.registration_end_time, ERROR_REGISTRATION_ENDED);
 
-        let token_amount = &payment.amount * ONE / &launchpad.price;
         let caller = self.blockchain().get_caller();
         require!(!launchpad.kyc_enforced || self.whitelisted_users(id).contains(&caller), ERROR_NOT_WHITELISTED);
+        require!(self.lottery_entry_index(id, &caller).is_empty(), ERROR_ALREADY_REGISTERED);
+        require!(*self.call_value().egld_value() == 0, ERROR_EGLD_NOT_ACCEPTED);
+
+        let payments = self.call_value().all_esdt_transfers();
+        let tickets = if lottery.tokens_per_ticket > 0 {
+            let payment = self.call_value().single_esdt();
+            require!(payment.token_identifier == self.governance_token().get(), ERROR_WRONG_TOKEN);
 
-        let old_bought_amount = self.user_participation(&caller, id).get();
+            let tickets = (&payment.amount / &lottery.tokens_per_ticket).to_u64().unwrap_or_default();
+            require!(tickets > 0, ERROR_NO_TICKETS);
+
+            self.lottery_stakes(id, &caller).set(&payment.amount);
+            tickets
+        } else {
+            require!(payments.is_empty(), ERROR_WRONG_TOKEN);
+
+            1
+        };
+
+        let first_ticket = self.lottery_total_tickets(id).get();
+        let index = self.lottery_entries(id).push(&LotteryEntry {
+            user: caller.clone(),
+            first_ticket,
+            tickets,
+        });
+        self.lottery_entry_index(id, &caller).set(index);
+        self.lottery_total_tickets(id).set(first_ticket + tickets);
+    }
+
+    // any user can draw after the registration ends. large lotteries may need several calls.
+    // contracts are rejected so they cannot revert draws with an unwanted outcome
+    #[endpoint(drawLottery)]
+    fn draw_lottery(&self, id: u64) {
+        require!(self.state().get() == State::Active, ERROR_NOT_ACTIVE);
+        require!(!self.launchpads(id).is_empty(), ERROR_LAUNCHPAD_NOT_FOUND);
+        require!(!self.blockchain().is_smart_contract(&self.blockchain().get_caller()), ERROR_SC_CALLER);
+
+        let lottery = self.get_lottery(id);
         require!(
-            &token_amount + &old_bought_amount >= launchpad.min_buy_amount,
-            ERROR_LOW_AMOUNT
+            self.blockchain().get_block_timestamp() >= lottery.registration_end_time,
+            ERROR_REGISTRATION_NOT_ENDED
         );
+        require!(!self.is_lottery_drawn(id), ERROR_LOTTERY_DRAWN);
+
+        let entries = self.lottery_entries(id);
+        let mut winners = self.lottery_winners(id);
+        if entries.len() <= lottery.winners_count {
+            for entry in entries.iter() {
+                winners.insert(entry.user);
+            }
+
+            return
+        }
+
+        let total_tickets = self.lottery_total_tickets(id).get();
+        let mut randomness = RandomnessSource::<Self::Api>::new();
+        for _ in 0..MAX_DRAW_ATTEMPTS {
+            if winners.len() >= lottery.winners_count {
+                break
+            }
+
+            let ticket = randomness.next_u64_in_range(0, total_tickets);
+            winners.insert(entries.get(self.find_lottery_entry(id, ticket)).user);
+        }
+    }
+
+    #[endpoint(withdrawLotteryStake)]
+    fn withdraw_lottery_stake(&self, id: u64) {
+        require!(self.state().get() == State::Active, ERROR_NOT_ACTIVE);
+        require!(!self.launchpads(id).is_empty(), ERROR_LAUNCHPAD_NOT_FOUND);
+        require!(self.is_lottery_drawn(id) || self.launchpads(id).get().cancelled, ERROR_LOTTERY_NOT_DRAWN);
+
+        let caller = self.blockchain().get_caller();
+        let stake = self.lottery_stakes(id, &caller).take();
+        require!(stake > 0, ERROR_NOTHING_TO_REFUND);
+
+        self.send().direct_esdt(
+            &caller,
+            &self.governance_token().get(),
+            0,
+            &stake
+        );
+    }
+
+    #[payable("*")]
+    #[endpoint(buy)]
+    fn buy(&self, id: u64, min_token_amount: OptionalValue<BigUint>) {
+        require!(self.state().get() == State::Active, ERROR_NOT_ACTIVE);
+        require!(!self.launchpads(id).is_empty(), ERROR_LAUNCHPAD_NOT_FOUND);
+
+        let payment = self.call_value().egld_or_single_esdt();
+        self.process_buy(id, &self.blockchain().get_caller(), &payment, min_token_amount);
+    }
+
+    // swaps the payment to the launchpad payment token through the DEX and buys with the result.
+    // the whole transaction reverts (refunding the payment) if less than min_token_out tokens are bought.
+    // the unused part of the swapped amount is refunded in the launchpad payment token
+    #[payable("*")]
+    #[endpoint(buyWithSwap)]
+    fn buy_with_swap(&self, id: u64, min_token_out: BigUint) {
+        require!(self.state().get() == State::Active, ERROR_NOT_ACTIVE);
+        require!(!self.launchpads(id).is_empty(), ERROR_LAUNCHPAD_NOT_FOUND);
+        require!(!self.dex_sc().is_empty(), ERROR_DEX_NOT_SET);
+
+        let launchpad = self.launchpads(id).get();
         require!(
-            &token_amount + &old_bought_amount <= launchpad.max_buy_amount,
-            ERROR_HIGH_AMOUNT
+            launchpad.sale_type == SaleType::FixedPrice ||
+                launchpad.sale_type == SaleType::DutchAuction ||
+                launchpad.sale_type == SaleType::BondingCurve ||
+                launchpad.sale_type == SaleType::Lottery,
+            ERROR_WRONG_SALE_TYPE
         );
+        require!(launchpad.payment_token.is_esdt(), ERROR_WRONG_TOKEN);
+
+        let payment = self.call_value().single_esdt();
         require!(
-            &token_amount + &launchpad.total_sold <= launchpad.amount,
-            ERROR_INSUFFICIENT_FUNDS
+            EgldOrEsdtTokenIdentifier::esdt(payment.token_identifier.clone()) != launchpad.payment_token,
+            ERROR_WRONG_TOKEN
         );
+        require!(payment.amount > 0, ERROR_ZERO_AMOUNT);
 
-        self.send().direct_esdt(
-            &caller,
-            &launchpad.token,
-            0,
-            &token_amount
+        let balance_before = self.blockchain().get_sc_balance(&launchpad.payment_token, 0);
+        self.dex_contract_proxy()
+            .contract(self.dex_sc().get())
+            .swap(launchpad.payment_token.clone().unwrap_esdt())
+            .with_esdt_transfer(payment)
+            .execute_on_dest_context::<()>();
+        let swapped_amount = self.blockchain().get_sc_balance(&launchpad.payment_token, 0) - balance_before;
+        require!(swapped_amount > 0, ERROR_SWAP_FAILED);
+
+        let swapped = EgldOrEsdtTokenPayment::new(launchpad.payment_token, 0, swapped_amount);
+        self.process_buy(id, &self.blockchain().get_caller(), &swapped, OptionalValue::Some(min_token_out));
+    }
+
+    fn process_buy(
+        &self,
+        id: u64,
+        caller: &ManagedAddress,
+        payment: &EgldOrEsdtTokenPayment,
+        min_token_amount: OptionalValue<BigUint>
+    ) {
+        let mut launchpad = self.launchpads(id).get();
+        require!(launchpad.is_active(self.blockchain().get_block_timestamp()), ERROR_LAUNCHPAD_INACTIVE);
+        require!(
+            payment.token_identifier == launchpad.payment_token ||
+                self.payment_token_prices(id).contains_key(&payment.token_identifier),
+            ERROR_WRONG_TOKEN
+        );
+        require!(!launchpad.kyc_enforced || self.whitelisted_users(id).contains(caller), ERROR_NOT_WHITELISTED);
+        require!(!launchpad.mints_on_buy() || self.has_mint_roles(&launchpad.token), ERROR_MISSING_MINT_ROLES);
+
+        match launchpad.sale_type {
+            SaleType::FixedPrice | SaleType::DutchAuction | SaleType::BondingCurve | SaleType::Lottery => {
+                require!(
+                    launchpad.sale_type != SaleType::Lottery || self.lottery_winners(id).contains(caller),
+                    ERROR_NOT_WINNER
+                );
+
+                let token_amount = self.buy_tokens(&mut launchpad, caller, &payment.token_identifier, &payment.amount);
+                if let OptionalValue::Some(min_token_amount) = min_token_amount {
+                    require!(token_amount >= min_token_amount, ERROR_SLIPPAGE);
+                }
+            },
+            SaleType::ProRata | SaleType::FairLaunch => self.commit_payment(&mut launchpad, caller, &payment.amount),
+            SaleType::BatchAuction => sc_panic!(ERROR_WRONG_SALE_TYPE),
+        }
+        self.launchpads(id).set(launchpad);
+
+        self.user_launchpads(caller).insert(id);
+        self.launchpad_users(id).insert(caller.clone());
+    }
+
+    // fills as much as the remaining supply and the user allowance permit and refunds the unused payment.
+    // if the launchpad has rounds, the currently active round sets the price, limits and allocation.
+    // dutch auctions buy at the current price and record the clearing price when selling out.
+    // bonding curves integrate the price over the bought amount.
+    // payments in an extra payment token use that token's price and are accounted separately
+    fn buy_tokens(
+        &self,
+        launchpad: &mut Launchpad<Self::Api>,
+        caller: &ManagedAddress,
+        payment_token: &EgldOrEsdtTokenIdentifier,
+        payment_amount: &BigUint
+    ) -> BigUint {
+        let round_id = self.get_active_round_id(launchpad.id);
+        let mut round = if round_id > 0 {
+            self.rounds(launchpad.id).get(round_id)
+        } else {
+            require!(self.rounds(launchpad.id).is_empty(), ERROR_NO_ACTIVE_ROUND);
+
+            Round {
+                price: launchpad.price.clone(),
+                start_time: launchpad.start_time,
+                end_time: launchpad.end_time,
+                min_buy_amount: launchpad.min_buy_amount.clone(),
+                max_buy_amount: launchpad.max_buy_amount.clone(),
+                allocation: launchpad.amount.clone(),
+                whitelist_enforced: false,
+                total_raised: launchpad.total_raised.clone(),
+                total_sold: launchpad.total_sold.clone(),
+            }
+        };
+        if launchpad.sale_type == SaleType::DutchAuction {
+            round.price = self.get_dutch_auction_price(launchpad, self.blockchain().get_block_timestamp());
+        }
+        let is_primary_token = *payment_token == launchpad.payment_token;
+        if !is_primary_token {
+            round.price = self.get_payment_token_price(launchpad, payment_token);
+        }
+        require!(
+            !round.whitelist_enforced || self.round_whitelisted_users(launchpad.id, round_id).contains(caller),
+            ERROR_NOT_WHITELISTED
+        );
+
+        let participation_mapper = if round_id > 0 {
+            self.user_round_participation(caller, launchpad.id, round_id)
+        } else {
+            self.user_participation(caller, launchpad.id)
+        };
+        let old_bought_amount = participation_mapper.get();
+        require!(old_bought_amount < round.max_buy_amount, ERROR_HIGH_AMOUNT);
+
+        let left_amount = core::cmp::min(
+            &launchpad.amount - &launchpad.total_sold,
+            &round.allocation - &round.total_sold
+        );
+        let allowance = &round.max_buy_amount - &old_bought_amount;
+        let max_amount = core::cmp::min(left_amount.clone(), allowance);
+        let (token_amount, cost) = if launchpad.sale_type == SaleType::BondingCurve {
+            self.get_bonding_curve(launchpad.id)
+                .get_buy_amount(&launchpad.price, &launchpad.total_sold, payment_amount, &max_amount, &launchpad.get_token_unit())
+        } else {
+            let token_amount = core::cmp::min(launchpad.get_token_amount(payment_amount, &round.price), max_amount);
+            let cost = launchpad.get_payment_amount(&token_amount, &round.price);
+
+            (token_amount, cost)
+        };
+        require!(token_amount > 0, ERROR_ZERO_AMOUNT);
+        require!(
+            &token_amount + &old_bought_amount >= round.min_buy_amount || token_amount == left_amount,
+            ERROR_LOW_AMOUNT
         );
 
-        launchpad.total_raised += payment.amount;
+        let change = payment_amount - &cost;
+        if change > 0 {
+            self.send().direct(
+                caller,
+                payment_token,
+                0,
+                &change
+            );
+        }
+        if launchpad.delivers_on_buy() {
+            self.send_sale_tokens(launchpad, caller, &token_amount);
+        }
+
+        if round_id > 0 {
+            round.total_raised += &cost;
+            round.total_sold += &token_amount;
+            self.rounds(launchpad.id).set(round_id, &round);
+            participation_mapper.update(|value| *value += &token_amount);
+        }
+        if is_primary_token {
+            launchpad.total_raised += &cost;
+            self.add_total_raised(payment_token, &cost);
+            self.user_payment(caller, launchpad.id).update(|value| *value += &cost);
+        } else {
+            self.token_raised(launchpad.id, payment_token).update(|value| *value += &cost);
+            self.add_total_raised(payment_token, &cost);
+            self.user_token_payment(caller, launchpad.id, payment_token).update(|value| *value += &cost);
+        }
+        let price = if launchpad.sale_type == SaleType::BondingCurve {
+            &cost * &launchpad.get_token_unit() / &token_amount
+        } else {
+            round.price.clone()
+        };
+        self.record_purchase(launchpad.id, caller, payment_token, &cost, &BigUint::zero(), &token_amount, price);
+
         launchpad.total_sold += &token_amount;
+        if launchpad.sale_type == SaleType::DutchAuction && launchpad.total_sold == launchpad.amount {
+            launchpad.clearing_price = round.price;
+        }
+
+        self.user_participation(caller, launchpad.id).update(|value| *value += &token_amount);
+        self.buy_event(launchpad.id, caller, payment_token, &cost, &token_amount);
+
+        token_amount
+    }
+
+    // pro-rata: the min/max buy amounts apply to the committed amount converted at the sale price.
+    // fair launch: the price is not known yet, so the min/max buy amounts are in payment tokens
+    fn commit_payment(&self, launchpad: &mut Launchpad<Self::Api>, caller: &ManagedAddress, payment_amount: &BigUint) {
+        let committed = self.user_payment(caller, launchpad.id).get() + payment_amount;
+        let committed_amount = if launchpad.sale_type == SaleType::FairLaunch {
+            committed.clone()
+        } else {
+            launchpad.get_token_amount(&committed, &launchpad.price)
+        };
+        require!(committed_amount >= launchpad.min_buy_amount, ERROR_LOW_AMOUNT);
+        require!(committed_amount <= launchpad.max_buy_amount, ERROR_HIGH_AMOUNT);
+
+        launchpad.total_raised += payment_amount;
+        self.add_total_raised(&launchpad.payment_token, payment_amount);
+        self.user_payment(caller, launchpad.id).set(committed);
+        self.commit_event(launchpad.id, caller, &launchpad.payment_token, payment_amount);
+    }
+
+    // commits (or replaces) the caller's sealed bid. the deposit is added to any previous deposit and
+    // must cover price * quantity when revealing
+    #[payable("*")]
+    #[endpoint(commitBid)]
+    fn commit_bid(&self, id: u64, bid_hash: ManagedByteArray<Self::Api, 32>) {
+        require!(self.state().get() == State::Active, ERROR_NOT_ACTIVE);
+        require!(!self.launchpads(id).is_empty(), ERROR_LAUNCHPAD_NOT_FOUND);
+
+        let mut launchpad = self.launchpads(id).get();
+        require!(launchpad.sale_type == SaleType::BatchAuction, ERROR_WRONG_SALE_TYPE);
+
+        require!(launchpad.has_token(), ERROR_TOKEN_NOT_SET);
+        require!(!launchpad.cancelled, ERROR_LAUNCHPAD_CANCELLED);
+
+        let auction = self.get_batch_auction(id);
+        let now = self.blockchain().get_block_timestamp();
+        require!(now >= launchpad.start_time && now <= auction.commit_end_time, ERROR_NOT_COMMIT_PERIOD);
+
+        let payment = self.call_value().egld_or_single_esdt();
+        require!(payment.token_identifier == launchpad.payment_token, ERROR_WRONG_TOKEN);
+
+        let caller = self.blockchain().get_caller();
+        require!(!launchpad.kyc_enforced || self.whitelisted_users(id).contains(&caller), ERROR_NOT_WHITELISTED);
+        require!(!launchpad.mints_on_buy() || self.has_mint_roles(&launchpad.token), ERROR_MISSING_MINT_ROLES);
+        require!(
+            self.launchpad_users(id).contains(&caller) || self.launchpad_users(id).len() < MAX_AUCTION_BIDS,
+            ERROR_TOO_MANY_BIDS
+        );
+
+        self.bid_hashes(id, &caller).set(bid_hash);
+        self.user_payment(&caller, id).update(|value| *value += &payment.amount);
+        launchpad.total_raised += &payment.amount;
+        self.add_total_raised(&payment.token_identifier, &payment.amount);
+        self.commit_event(id, &caller, &payment.token_identifier, &payment.amount);
         self.launchpads(id).set(launchpad);
 
-        self.user_participation(&caller, id).update(|value| *value += &token_amount);
         self.user_launchpads(&caller).insert(id);
         self.launchpad_users(id).insert(caller);
     }
 
+    #[endpoint(revealBid)]
+    fn reveal_bid(&self, id: u64, price: BigUint, quantity: BigUint, salt: ManagedBuffer) {
+        require!(self.state().get() == State::Active, ERROR_NOT_ACTIVE);
+        require!(!self.launchpads(id).is_empty(), ERROR_LAUNCHPAD_NOT_FOUND);
+
+        let launchpad = self.launchpads(id).get();
+        require!(launchpad.sale_type == SaleType::BatchAuction, ERROR_WRONG_SALE_TYPE);
+
+        let auction = self.get_batch_auction(id);
+        let now = self.blockchain().get_block_timestamp();
+        require!(now > auction.commit_end_time && now <= launchpad.end_time, ERROR_NOT_REVEAL_PERIOD);
+
+        let caller = self.blockchain().get_caller();
+        require!(!self.bid_hashes(id, &caller).is_empty(), ERROR_BID_NOT_FOUND);
+        require!(
+            self.bid_hashes(id, &caller).get() == self.compute_bid_hash(price.clone(), quantity.clone(), salt),
+            ERROR_WRONG_BID_HASH
+        );
+        require!(price >= launchpad.price, ERROR_LOW_BID_PRICE);
+        require!(quantity >= launchpad.min_buy_amount, ERROR_LOW_AMOUNT);
+        require!(quantity <= launchpad.max_buy_amount, ERROR_HIGH_AMOUNT);
+        require!(
+            launchpad.get_payment_amount(&quantity, &price) <= self.user_payment(&caller, id).get(),
+            ERROR_INSUFFICIENT_DEPOSIT
+        );
+
+        self.bid_hashes(id, &caller).clear();
+        self.revealed_bids(id).push(&Bid {
+            bidder: caller,
+            price,
+            quantity,
+        });
+    }
+
+    // fills the revealed bids from the highest price down until the supply is exhausted. equal prices
+    // are filled in reveal order. all winners pay the lowest winning price; unrevealed and losing bids
+    // are refunded when settling
+    #[endpoint(finalizeAuction)]
+    fn finalize_auction(&self, id: u64) {
+        require!(self.state().get() == State::Active, ERROR_NOT_ACTIVE);
+        require!(!self.launchpads(id).is_empty(), ERROR_LAUNCHPAD_NOT_FOUND);
+
+        let mut launchpad = self.launchpads(id).get();
+        require!(launchpad.sale_type == SaleType::BatchAuction, ERROR_WRONG_SALE_TYPE);
+        require!(launchpad.end_time < self.blockchain().get_block_timestamp(), ERROR_LAUNCHPAD_NOT_ENDED);
+        require!(!launchpad.is_finalized(), ERROR_AUCTION_FINALIZED);
+
+        let mut bids: ManagedVec<Bid<Self::Api>> = ManagedVec::new();
+        for bid in self.revealed_bids(id).iter() {
+            bids.push(bid);
+        }
+
+        let mut clearing_price = launchpad.price.clone();
+        while !bids.is_empty() {
+            let left_amount = &launchpad.amount - &launchpad.total_sold;
+            if left_amount == 0 {
+                break
+            }
+
+            let mut best_idx = 0;
+            for idx in 1..bids.len() {
+                if bids.get(idx).price > bids.get(best_idx).price {
+                    best_idx = idx;
+                }
+            }
+            let bid = bids.get(best_idx).clone();
+            bids.remove(best_idx);
+
+            let filled_amount = core::cmp::min(bid.quantity, left_amount);
+            self.user_participation(&bid.bidder, id).set(&filled_amount);
+            launchpad.total_sold += filled_amount;
+            clearing_price = bid.price;
+        }
+        launchpad.clearing_price = clearing_price;
+        self.refresh_status_index(&launchpad);
+        self.launchpads(id).set(launchpad);
+    }
+
+    // pro-rata sales: after end time each buyer receives its share of tokens and the unused payment.
+    // dutch auctions: after end time each buyer receives the bought tokens and the difference between
+    // the paid amount and the clearing price.
+    // fair launches: after end time each buyer receives amount * contribution / total_raised tokens.
+    // batch auctions: after finalization each bidder receives the filled quantity and the unused deposit
+    #[endpoint(settle)]
+    fn settle(&self, id: u64) {
+        require!(self.state().get() == State::Active, ERROR_NOT_ACTIVE);
+        require!(!self.launchpads(id).is_empty(), ERROR_LAUNCHPAD_NOT_FOUND);
+
+        let mut launchpad = self.launchpads(id).get();
+        require!(launchpad.needs_settlement(), ERROR_WRONG_SALE_TYPE);
+        require!(!launchpad.cancelled, ERROR_LAUNCHPAD_CANCELLED);
+        require!(launchpad.end_time < self.blockchain().get_block_timestamp(), ERROR_LAUNCHPAD_NOT_ENDED);
+        require!(launchpad.is_finalized(), ERROR_AUCTION_NOT_FINALIZED);
+        require!(launchpad.is_soft_cap_reached(), ERROR_SOFT_CAP_NOT_REACHED);
+
+        let caller = self.blockchain().get_caller();
+        require!(!self.user_settled(&caller, id).get(), ERROR_ALREADY_SETTLED);
+
+        let paid = self.user_payment(&caller, id).get();
+        require!(paid > 0, ERROR_NOTHING_TO_SETTLE);
+
+        let bought = self.user_participation(&caller, id).get();
+        let (token_amount, refund_amount) = launchpad.get_user_allocation(&paid, &bought);
+        self.user_settled(&caller, id).set(true);
+        self.user_payment(&caller, id).set(&paid - &refund_amount);
+        if launchpad.is_commitment_based() {
+            self.user_participation(&caller, id).set(&token_amount);
+            launchpad.total_sold += &token_amount;
+            self.launchpads(id).set(&launchpad);
+        }
+
+        if refund_amount > 0 {
+            self.send().direct(
+                &caller,
+                &launchpad.payment_token,
+                0,
+                &refund_amount
+            );
+        }
+        if !launchpad.escrow {
+            self.send_sale_tokens(&launchpad, &caller, &token_amount);
+        }
+        // dutch auction purchases were already recorded when buying
+        if launchpad.sale_type == SaleType::DutchAuction {
+            if refund_amount > 0 {
+                self.record_purchase(
+                    id,
+                    &caller,
+                    &launchpad.payment_token,
+                    &BigUint::zero(),
+                    &refund_amount,
+                    &BigUint::zero(),
+                    launchpad.get_clearing_price()
+                );
+            }
+        } else if token_amount > 0 {
+            self.record_purchase(
+                id,
+                &caller,
+                &launchpad.payment_token,
+                &(&paid - &refund_amount),
+                &BigUint::zero(),
+                &token_amount,
+                launchpad.get_clearing_price()
+            );
+        }
+        self.refresh_status_index(&launchpad);
+        self.settle_event(id, &caller, &token_amount, &refund_amount);
+    }
+
+    #[endpoint(claim)]
+    fn claim(&self, id: u64) {
+        require!(self.state().get() == State::Active, ERROR_NOT_ACTIVE);
+        require!(!self.launchpads(id).is_empty(), ERROR_LAUNCHPAD_NOT_FOUND);
+
+        let launchpad = self.launchpads(id).get();
+        require!(launchpad.vesting.is_none(), ERROR_VESTED_LAUNCHPAD);
+
+        self.claim_unlocked_tokens(&launchpad);
+    }
+
+    #[endpoint(claimVested)]
+    fn claim_vested(&self, id: u64) {
+        require!(self.state().get() == State::Active, ERROR_NOT_ACTIVE);
+        require!(!self.launchpads(id).is_empty(), ERROR_LAUNCHPAD_NOT_FOUND);
+
+        let launchpad = self.launchpads(id).get();
+        require!(launchpad.vesting.is_some(), ERROR_NOT_VESTED);
+
+        self.claim_unlocked_tokens(&launchpad);
+    }
+
+    fn claim_unlocked_tokens(&self, launchpad: &Launchpad<Self::Api>) {
+        require!(launchpad.escrow, ERROR_NOT_ESCROW);
+
+        let now = self.blockchain().get_block_timestamp();
+        require!(launchpad.is_claim_open(now), ERROR_CLAIM_NOT_OPEN);
+
+        let caller = self.blockchain().get_caller();
+        let bought = self.user_participation(&caller, launchpad.id).get();
+        let claimable = launchpad.get_claimable_amount(&bought, &self.user_claimed(&caller, launchpad.id).get(), now);
+        require!(claimable > 0, ERROR_NOTHING_TO_CLAIM);
+
+        self.user_claimed(&caller, launchpad.id).update(|value| *value += &claimable);
+        self.send_sale_tokens(launchpad, &caller, &claimable);
+        self.refresh_status_index(launchpad);
+        self.claim_event(launchpad.id, &caller, &claimable);
+    }
+
+    // buyers of a failed or cancelled launchpad return the tokens they received and get their payment back.
+    // the launchpad owner reclaims the deposited tokens that were not sold (or were returned)
+    #[payable("*")]
+    #[endpoint(claimRefund)]
+    fn claim_refund(&self, id: u64) {
+        require!(self.state().get() == State::Active, ERROR_NOT_ACTIVE);
+        require!(!self.launchpads(id).is_empty(), ERROR_LAUNCHPAD_NOT_FOUND);
+
+        let mut launchpad = self.launchpads(id).get();
+        let status = launchpad.get_status(self.blockchain().get_block_timestamp());
+        require!(status == Status::Failed || status == Status::Cancelled, ERROR_LAUNCHPAD_NOT_FAILED);
+
+        // settlements and claims only happen before a cancellation, never in failed launchpads
+        let caller = self.blockchain().get_caller();
+        let paid = self.user_payment(&caller, id).take();
+        let bought = self.user_participation(&caller, id).take();
+        let delivered = if launchpad.escrow {
+            self.user_claimed(&caller, id).take()
+        } else if launchpad.delivers_on_buy() || self.user_settled(&caller, id).get() {
+            bought.clone()
+        } else {
+            BigUint::zero()
+        };
+        require!(*self.call_value().egld_value() == 0, ERROR_EGLD_NOT_ACCEPTED);
+        let payments = self.call_value().all_esdt_transfers();
+        if delivered > 0 {
+            require!(payments.len() == 1, ERROR_WRONG_RETURNED_AMOUNT);
+
+            let returned = payments.get(0);
+            require!(returned.token_identifier == launchpad.token, ERROR_WRONG_TOKEN);
+            require!(returned.amount == delivered, ERROR_WRONG_RETURNED_AMOUNT);
+            if launchpad.mints_on_buy() {
+                self.send().esdt_local_burn(&launchpad.token, 0, &delivered);
+            }
+        } else {
+            require!(payments.is_empty(), ERROR_WRONG_TOKEN);
+        }
+
+        let unsold_amount = if caller == launchpad.owner && !launchpad.mints_on_buy() {
+            &launchpad.amount - &launchpad.total_sold + &bought
+        } else {
+            BigUint::zero()
+        };
+        let mut refunded = paid > 0 || unsold_amount > 0;
+
+        if paid > 0 {
+            self.send().direct(
+                &caller,
+                &launchpad.payment_token,
+                0,
+                &paid
+            );
+        }
+        for token in self.payment_token_prices(id).keys() {
+            let token_paid = self.user_token_payment(&caller, id, &token).take();
+            if token_paid == 0 {
+                continue
+            }
+
+            self.send().direct(
+                &caller,
+                &token,
+                0,
+                &token_paid
+            );
+            self.token_raised(id, &token).update(|value| *value -= &token_paid);
+            self.sub_total_raised(&token, &token_paid);
+            refunded = true;
+        }
+        require!(refunded, ERROR_NOTHING_TO_REFUND);
+        if unsold_amount > 0 {
+            self.send().direct_esdt(
+                &caller,
+                &launchpad.token,
+                0,
+                &unsold_amount
+            );
+        }
+
+        launchpad.total_raised -= &paid;
+        self.sub_total_raised(&launchpad.payment_token, &paid);
+        if status == Status::Failed {
+            self.set_status_index(id, Status::Failed);
+        }
+        launchpad.total_sold -= &bought;
+        launchpad.amount -= &unsold_amount;
+        self.refund_event(id, &caller, &delivered);
+        self.launchpads(id).set(launchpad);
+    }
+
     #[payable("EGLD")]
     #[endpoint(deployFranchise)]
     fn deploy_franchise(&self, id: u64) -> ManagedAddress {
@@ -200,6 +1346,10 @@ pub trait TFNLaunchpadContract<ContractReader>:
         let mut launchpad = self.launchpads(id).get();
         require!(launchpad.end_time < self.blockchain().get_block_timestamp(), ERROR_LAUNCHPAD_NOT_ENDED);
         require!(!launchpad.deployed, ERROR_ALREADY_DEPLOYED);
+        require!(!launchpad.cancelled, ERROR_LAUNCHPAD_CANCELLED);
+        require!(launchpad.has_token(), ERROR_TOKEN_NOT_SET);
+        require!(launchpad.is_finalized(), ERROR_AUCTION_NOT_FINALIZED);
+        require!(launchpad.is_soft_cap_reached(), ERROR_SOFT_CAP_NOT_REACHED);
 
         let main_dao_address = self.main_dao().get();
         let template_dao = self.dao_contract_proxy()
@@ -217,13 +1367,30 @@ pub trait TFNLaunchpadContract<ContractReader>:
                 CodeMetadata::UPGRADEABLE | CodeMetadata::READABLE | CodeMetadata::PAYABLE_BY_SC,
             );
 
+        let (sold_amount, raised_amount) = launchpad.get_allocation();
         let mut payments: ManagedVec<EsdtTokenPayment> = ManagedVec::new();
-        if launchpad.total_raised > 0 {
-            payments.push(EsdtTokenPayment::new(launchpad.payment_token.clone(), 0, launchpad.total_raised.clone()));
+        let mut raised: ManagedVec<EgldOrEsdtTokenPayment<Self::Api>> = ManagedVec::new();
+        raised.push(EgldOrEsdtTokenPayment::new(launchpad.payment_token.clone(), 0, raised_amount));
+        for token in self.payment_token_prices(id).keys() {
+            let amount = self.token_raised(id, &token).get();
+            raised.push(EgldOrEsdtTokenPayment::new(token, 0, amount));
+        }
+
+        let mut egld_amount = BigUint::zero();
+        for payment in raised.iter() {
+            if payment.amount == 0 {
+                continue
+            }
+
+            if payment.token_identifier.is_egld() {
+                egld_amount += &payment.amount;
+            } else {
+                payments.push(EsdtTokenPayment::new(payment.token_identifier.unwrap_esdt(), 0, payment.amount));
+            }
         }
 
-        let left_amount = &launchpad.amount - &launchpad.total_sold;
-        if left_amount > 0 {
+        let left_amount = &launchpad.amount - &sold_amount;
+        if left_amount > 0 && !launchpad.mints_on_buy() {
             payments.push(EsdtTokenPayment::new(launchpad.token.clone(), 0, left_amount.clone()));
         }
 
@@ -234,6 +1401,13 @@ pub trait TFNLaunchpadContract<ContractReader>:
                 .multi_esdt(payments)
                 .execute_on_dest_context::<()>();
         }
+        if egld_amount > 0 {
+            self.franchise_dao_contract_proxy()
+                .contract(new_address.clone())
+                .add_funds()
+                .with_egld_transfer(egld_amount)
+                .execute_on_dest_context::<()>();
+        }
 
         self.dao_contract_proxy()
             .contract(main_dao_address.clone())
@@ -283,13 +1457,69 @@ pub trait TFNLaunchpadContract<ContractReader>:
             .gas(GAS_LIMIT_FOR_CREATE_PAIR)
             .execute_on_dest_context::<()>();
 
+        // the tokens that are still to be settled or claimed are minted now, so the token roles can be released
+        if launchpad.mints_on_buy() {
+            let minted_amount = self.minted_tokens(id).get();
+            if sold_amount > minted_amount {
+                let mint_amount = &sold_amount - &minted_amount;
+                self.send().esdt_local_mint(&launchpad.token, 0, &mint_amount);
+                self.minted_tokens(id).set(&sold_amount);
+            }
+        }
+
         launchpad.deployed = true;
+        if launchpad.escrow && launchpad.claim_start == 0 {
+            launchpad.claim_start = self.blockchain().get_block_timestamp();
+        }
         self.deployed_launchpads(new_address.clone()).set(id);
+        self.franchise_address(id).set(&new_address);
         self.launchpads(id).set(launchpad);
+        self.set_status_index(id, Status::Deployed);
+        self.deploy_franchise_event(id, &new_address, identity_id);
 
         new_address
     }
 
+    // hands a token issued by a deployed launchpad over to its franchise DAO. the first call removes
+    // the roles of the launchpad, the next one transfers the token ownership
+    #[endpoint(releaseLaunchpadToken)]
+    fn release_launchpad_token(&self, id: u64) {
+        require!(self.state().get() == State::Active, ERROR_NOT_ACTIVE);
+        require!(!self.launchpads(id).is_empty(), ERROR_LAUNCHPAD_NOT_FOUND);
+
+        let launchpad = self.launchpads(id).get();
+        require!(launchpad.deployed, ERROR_NOT_DEPLOYED);
+        require!(self.token_issued(id).get(), ERROR_TOKEN_NOT_ISSUED);
+        require!(!self.token_released(id).get(), ERROR_TOKEN_RELEASED);
+
+        let roles = self.blockchain().get_esdt_local_roles(&launchpad.token);
+        if roles.iter_roles().next().is_some() {
+            self.tx()
+                .to(ESDTSystemSCAddress)
+                .typed(system_proxy::ESDTSystemSCProxy)
+                .unset_special_roles(
+                    &self.blockchain().get_sc_address(),
+                    &launchpad.token,
+                    roles.iter_roles().cloned()
+                )
+                .async_call_and_exit();
+        }
+
+        self.tx()
+            .to(ESDTSystemSCAddress)
+            .typed(system_proxy::ESDTSystemSCProxy)
+            .transfer_ownership(&launchpad.token, &self.franchise_address(id).get())
+            .callback(self.callbacks().release_launchpad_token_callback(id))
+            .async_call_and_exit();
+    }
+
+    #[callback]
+    fn release_launchpad_token_callback(&self, id: u64, #[call_result] result: ManagedAsyncCallResult<()>) {
+        if let ManagedAsyncCallResult::Ok(()) = result {
+            self.token_released(id).set(true);
+        }
+    }
+
     #[endpoint(upgradeFranchise)]
     fn upgrade_franchise(&self, franchise_address: ManagedAddress, args: OptionalValue<ManagedArgBuffer<Self::Api>>) {
         let caller = self.blockchain().get_caller();
@@ -303,6 +1533,7 @@ pub trait TFNLaunchpadContract<ContractReader>:
             .contract(self.main_dao().get())
             .template_franchise_dao()
             .execute_on_dest_context();
+        self.upgrade_franchise_event(&franchise_address);
         let gas_left = self.blockchain().get_gas_left();
         self.tx()
             .to(franchise_address)